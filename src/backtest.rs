@@ -0,0 +1,341 @@
+use crate::benchmark::BenchmarkResult;
+use crate::config::{BacktestArgs, Config, HistoryArgs};
+use crate::fees::FeeBreakdown;
+use crate::metrics::Metrics;
+use crate::model::{AppData, PriceInfo};
+use crate::simulation::{fill, plan_price_update, plan_schedule, warm_up};
+use crate::tokens::TokenRegistry;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// One historical price of a Hermes feed.
+#[derive(Debug, Clone)]
+pub struct PricePoint {
+    pub time: DateTime<Utc>,
+    /// Feed name as listed under `hermes.feeds`.
+    pub feed: String,
+    pub feed_id: String,
+    pub price: PriceInfo,
+}
+
+/// Row of a price file. Other columns, such as Pyth's `conf`, are ignored.
+#[derive(Debug, Deserialize)]
+struct Row {
+    timestamp: RawTime,
+    price: f64,
+    #[serde(default)]
+    feed: Option<String>,
+}
+
+/// Unix seconds or an RFC 3339 timestamp.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawTime {
+    Seconds(f64),
+    Text(String),
+}
+
+impl RawTime {
+    fn parse(&self) -> Result<DateTime<Utc>> {
+        let seconds = match self {
+            RawTime::Seconds(seconds) => *seconds,
+            RawTime::Text(text) => match text.trim().parse::<f64>() {
+                Ok(seconds) => seconds,
+                Err(_) => {
+                    return DateTime::parse_from_rfc3339(text.trim())
+                        .map(|time| time.with_timezone(&Utc))
+                        .with_context(|| format!("invalid timestamp `{text}`"));
+                }
+            },
+        };
+        DateTime::from_timestamp_millis((seconds * 1000.0).round() as i64)
+            .ok_or_else(|| anyhow!("timestamp {seconds} is out of range"))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub ticks: usize,
+    pub strategies: Vec<StrategyReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StrategyReport {
+    pub label: String,
+    pub pair: String,
+    pub initial_equity_usd: Option<f64>,
+    pub final_equity_usd: Option<f64>,
+    /// Balances at the end of the run in whole tokens, keyed by symbol.
+    pub final_balances: BTreeMap<String, f64>,
+    pub metrics: Option<Metrics>,
+    pub benchmarks: Vec<BenchmarkResult>,
+    /// Halt and rejection counts of the strategy's risk limits, if any.
+    pub risk: Option<serde_json::Value>,
+    pub trades: Vec<Trade>,
+    pub equity_curve: Vec<EquityPoint>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Trade {
+    pub time: DateTime<Utc>,
+    pub direction: String,
+    pub price: f64,
+    pub amount_in: f64,
+    pub input: String,
+    pub amount_out: f64,
+    pub output: String,
+    pub fees: FeeBreakdown,
+    pub price_impact_pct: Option<f64>,
+    pub realized_profit: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct EquityPoint {
+    pub time: DateTime<Utc>,
+    pub equity_usd: f64,
+}
+
+pub fn run_cli(config: &Config, args: &BacktestArgs) -> Result<()> {
+    let prices = load_prices(config, &args.history)?;
+    let report = run(config, &prices)?;
+    for strategy in &report.strategies {
+        let change = match (strategy.initial_equity_usd, strategy.final_equity_usd) {
+            (Some(initial), Some(last)) if initial > 0.0 => {
+                format!(
+                    "{initial:.2} → {last:.2} USD ({:+.2}%)",
+                    (last / initial - 1.0) * 100.0
+                )
+            }
+            _ => "unpriced".to_string(),
+        };
+        eprintln!(
+            "{}: {change}, {} trades",
+            strategy.label,
+            strategy.trades.len()
+        );
+    }
+
+    write_json(args.output.as_deref(), &report)
+}
+
+/// Writes `value` as pretty JSON to `path`, or to stdout without one.
+pub fn write_json(path: Option<&Path>, value: &impl Serialize) -> Result<()> {
+    let writer: Box<dyn Write> = match path {
+        Some(path) => Box::new(
+            File::create(path).with_context(|| format!("failed to create {}", path.display()))?,
+        ),
+        None => Box::new(io::stdout()),
+    };
+    let mut writer = BufWriter::new(writer);
+    serde_json::to_writer_pretty(&mut writer, value)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a CSV or JSONL price file, chosen by extension, ordered by time.
+/// Rows without a `feed` belong to the default feed.
+pub fn load_prices(config: &Config, history: &HistoryArgs) -> Result<Vec<PricePoint>> {
+    let path = history.prices.as_path();
+    let default_feed = history.feed.as_str();
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let rows = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("csv") => csv::Reader::from_reader(contents.as_bytes())
+            .deserialize::<Row>()
+            .enumerate()
+            .map(|(index, row)| {
+                row.with_context(|| format!("{}: row {}", path.display(), index + 1))
+            })
+            .collect::<Result<Vec<_>>>()?,
+        Some("jsonl" | "ndjson") => contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str::<Row>(line)
+                    .with_context(|| format!("{}: line {}", path.display(), index + 1))
+            })
+            .collect::<Result<Vec<_>>>()?,
+        _ => bail!("{} must be a .csv or .jsonl file", path.display()),
+    };
+
+    let mut prices = rows
+        .into_iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let context = || format!("{}: row {}", path.display(), index + 1);
+            let feed = row.feed.unwrap_or_else(|| default_feed.to_string());
+            if !(row.price.is_finite() && row.price > 0.0) {
+                bail!("{}: price must be positive, got {}", context(), row.price);
+            }
+            let time = row.timestamp.parse().with_context(context)?;
+            Ok(PricePoint {
+                feed_id: config
+                    .hermes
+                    .feed_id(&format!("{} feed", context()), &feed)?,
+                feed,
+                time,
+                price: PriceInfo {
+                    value: row.price,
+                    publish_time: Some(time.timestamp()),
+                },
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    prices.sort_by_key(|point| point.time);
+    Ok(prices)
+}
+
+/// Replays `prices` through fresh copies of the configured strategies on a
+/// simulated clock. Every price is followed by the scheduled hooks at its
+/// timestamp; swaps fill locally since there are no historical quotes.
+pub fn run(config: &Config, prices: &[PricePoint]) -> Result<Report> {
+    run_with_warmup(config, &[], prices)
+}
+
+/// Like [`run`], but first feeds `warmup` to the strategies without trading
+/// so their indicators are primed when `prices` starts. The report covers
+/// `prices` only.
+pub fn run_with_warmup(
+    config: &Config,
+    warmup: &[PricePoint],
+    prices: &[PricePoint],
+) -> Result<Report> {
+    let config = offline_config(config, prices)?;
+    let tokens = TokenRegistry::from_config(&config)?;
+    let mut data = AppData::new(&config, &tokens)?;
+    let mut curves = vec![Vec::new(); data.strategies.len()];
+
+    for point in warmup {
+        warm_up(&mut data, &point.feed_id, point.price.clone());
+    }
+
+    for point in prices {
+        let ready = plan_price_update(&mut data, &point.feed_id, point.price.clone(), point.time);
+        // Curves start before the strategy's first fill, so the costs of
+        // that fill count against its return.
+        sample_equity(&data, &tokens, &mut curves, point.time, true);
+        for action in ready {
+            fill(&mut data, &config, action, None, point.time);
+        }
+        for action in plan_schedule(&mut data, point.time) {
+            fill(&mut data, &config, action, None, point.time);
+        }
+        sample_equity(&data, &tokens, &mut curves, point.time, false);
+    }
+
+    let strategies = data
+        .strategies
+        .iter()
+        .zip(curves)
+        .enumerate()
+        .map(|(index, (strategy, equity_curve))| {
+            let final_balances = strategy
+                .wallet
+                .holdings()
+                .filter_map(|(mint, units)| {
+                    let token = tokens.by_mint(mint)?;
+                    Some((token.symbol.clone(), token.to_whole(units)))
+                })
+                .collect();
+            let trades = data
+                .history
+                .iter()
+                .filter(|record| record.strategy_index == index)
+                .map(|record| Trade {
+                    time: record.timestamp,
+                    direction: record.direction.clone(),
+                    price: record.price,
+                    amount_in: record.input_token.to_whole(record.amount_in),
+                    input: record.input_token.symbol.clone(),
+                    amount_out: record.output_token.to_whole(record.amount_out),
+                    output: record.output_token.symbol.clone(),
+                    fees: record.fees.clone(),
+                    price_impact_pct: record.price_impact_pct,
+                    realized_profit: record.realized_profit,
+                })
+                .collect();
+            StrategyReport {
+                label: strategy.strategy.label().to_string(),
+                pair: format!(
+                    "{}/{}",
+                    strategy.pair.base.symbol, strategy.pair.quote.symbol
+                ),
+                initial_equity_usd: equity_curve.first().map(|point| point.equity_usd),
+                final_equity_usd: equity_curve.last().map(|point| point.equity_usd),
+                final_balances,
+                metrics: strategy.metrics(),
+                benchmarks: strategy.benchmark_results(),
+                risk: strategy.risk.as_ref().map(|risk| risk.status()),
+                trades,
+                equity_curve,
+            }
+        })
+        .collect();
+
+    Ok(Report {
+        start: prices.first().map(|point| point.time),
+        end: prices.last().map(|point| point.time),
+        ticks: prices.len(),
+        strategies,
+    })
+}
+
+/// Appends each strategy's USD equity at `time` to its curve, or with
+/// `first_only` to the curves that are still empty.
+fn sample_equity(
+    data: &AppData,
+    tokens: &TokenRegistry,
+    curves: &mut [Vec<EquityPoint>],
+    time: DateTime<Utc>,
+    first_only: bool,
+) {
+    for (strategy, curve) in data.strategies.iter().zip(curves) {
+        if first_only && !curve.is_empty() {
+            continue;
+        }
+        if let Some(equity_usd) = strategy.wallet.value_usd(tokens, &data.latest_prices) {
+            curve.push(EquityPoint { time, equity_usd });
+        }
+    }
+}
+
+/// Keeps the whole trade list. Fails if a strategy holds or trades a token
+/// whose feed has no rows in the price file, since it could not be valued.
+fn offline_config(config: &Config, prices: &[PricePoint]) -> Result<Config> {
+    let feeds = prices
+        .iter()
+        .map(|point| point.feed.as_str())
+        .collect::<HashSet<_>>();
+    for strategy in &config.strategies {
+        let symbols = [&strategy.base, &strategy.quote]
+            .into_iter()
+            .chain(strategy.initial_balances.keys());
+        for symbol in symbols {
+            let feed = config
+                .tokens
+                .iter()
+                .find(|token| token.symbol == *symbol)
+                .and_then(|token| token.feed.as_deref());
+            if let Some(feed) = feed
+                && !feeds.contains(feed)
+            {
+                bail!(
+                    "strategy `{}` uses {symbol}, priced by feed `{feed}`, but the price file has no `{feed}` rows",
+                    strategy.label.as_deref().unwrap_or(&strategy.kind)
+                );
+            }
+        }
+    }
+    let mut config = config.clone();
+    config.simulation.max_history_entries = usize::MAX;
+    Ok(config)
+}
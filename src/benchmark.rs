@@ -0,0 +1,151 @@
+use crate::metrics::{Metrics, Performance};
+use crate::tokens::TradingPair;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Buy-and-hold portfolios a strategy is measured against. Each one starts
+/// with the strategy's equity at the first price it saw, split between base
+/// and quote without fees, and never trades.
+#[derive(Debug, Clone, Default)]
+pub struct Benchmarks {
+    portfolios: Vec<Portfolio>,
+}
+
+#[derive(Debug, Clone)]
+struct Portfolio {
+    kind: Kind,
+    base: f64,
+    quote: f64,
+    performance: Performance,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    HoldBase,
+    HoldQuote,
+    Balanced,
+}
+
+impl Kind {
+    const ALL: [Kind; 3] = [Kind::HoldBase, Kind::HoldQuote, Kind::Balanced];
+
+    fn base_weight(self) -> f64 {
+        match self {
+            Kind::HoldBase => 1.0,
+            Kind::HoldQuote => 0.0,
+            Kind::Balanced => 0.5,
+        }
+    }
+
+    fn name(self, pair: &TradingPair) -> String {
+        match self {
+            Kind::HoldBase => format!("HODL-{}", pair.base.symbol),
+            Kind::HoldQuote => format!("HODL-{}", pair.quote.symbol),
+            Kind::Balanced => "50/50".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkResult {
+    pub name: String,
+    /// Strategy return minus the benchmark's.
+    pub alpha: Option<f64>,
+    pub metrics: Option<Metrics>,
+}
+
+impl Benchmarks {
+    /// Marks every portfolio at a new pair price. The first call funds them
+    /// with `equity`, in quote.
+    pub fn observe(&mut self, equity: f64, price: f64, time: DateTime<Utc>) {
+        if self.portfolios.is_empty() {
+            self.portfolios = Kind::ALL
+                .into_iter()
+                .map(|kind| Portfolio {
+                    kind,
+                    base: equity * kind.base_weight() / price,
+                    quote: equity * (1.0 - kind.base_weight()),
+                    performance: Performance::default(),
+                })
+                .collect();
+        }
+        for portfolio in &mut self.portfolios {
+            portfolio
+                .performance
+                .observe(portfolio.base, portfolio.quote, price, time);
+        }
+    }
+
+    /// Benchmark metrics, with alpha against a strategy's `total_return`.
+    pub fn results(&self, pair: &TradingPair, total_return: Option<f64>) -> Vec<BenchmarkResult> {
+        self.portfolios
+            .iter()
+            .map(|portfolio| {
+                let metrics = portfolio
+                    .performance
+                    .metrics(portfolio.base, portfolio.quote);
+                let alpha = total_return
+                    .zip(metrics.as_ref())
+                    .map(|(total_return, metrics)| total_return - metrics.total_return);
+                BenchmarkResult {
+                    name: portfolio.kind.name(pair),
+                    alpha,
+                    metrics,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::tokens::TokenRegistry;
+    use chrono::TimeDelta;
+
+    fn close(actual: Option<f64>, expected: f64) -> bool {
+        actual.is_some_and(|actual| (actual - expected).abs() < 1e-9)
+    }
+
+    #[test]
+    fn portfolios_hold_their_split_from_the_first_price() {
+        let tokens = TokenRegistry::from_config(&Config::default()).unwrap();
+        let pair = tokens.pair("SOL", "USDC").unwrap();
+        let start = DateTime::<Utc>::UNIX_EPOCH;
+        let mut benchmarks = Benchmarks::default();
+        benchmarks.observe(200.0, 100.0, start);
+        // Later equity does not refund the portfolios.
+        benchmarks.observe(1_000.0, 150.0, start + TimeDelta::hours(1));
+
+        let results = benchmarks.results(&pair, Some(0.1));
+        let summary = results
+            .iter()
+            .map(|result| {
+                let metrics = result.metrics.as_ref().unwrap();
+                (result.name.as_str(), metrics.total_return, result.alpha)
+            })
+            .collect::<Vec<_>>();
+        let names = summary.iter().map(|(name, ..)| *name).collect::<Vec<_>>();
+        assert_eq!(names, ["HODL-SOL", "HODL-USDC", "50/50"]);
+        for ((_, total_return, alpha), expected) in summary.iter().zip([0.5, 0.0, 0.25]) {
+            assert!(close(Some(*total_return), expected), "{total_return}");
+            assert!(close(*alpha, 0.1 - expected), "{alpha:?}");
+        }
+    }
+
+    #[test]
+    fn no_strategy_return_means_no_alpha() {
+        let tokens = TokenRegistry::from_config(&Config::default()).unwrap();
+        let pair = tokens.pair("SOL", "USDC").unwrap();
+        let mut benchmarks = Benchmarks::default();
+        assert!(benchmarks.results(&pair, Some(0.1)).is_empty());
+        benchmarks.observe(100.0, 100.0, DateTime::<Utc>::UNIX_EPOCH);
+        assert!(
+            benchmarks
+                .results(&pair, None)
+                .iter()
+                .all(|result| result.alpha.is_none())
+        );
+    }
+}
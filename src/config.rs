@@ -1,41 +1,1204 @@
-use std::env;
+use crate::orders::Order;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::TimeDelta;
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum, builder::BoolishValueParser};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
 
-const HERMES_STREAM_BASE: &str = "https://hermes.pyth.network/v2/updates/price/stream";
-pub const MAX_HISTORY_ENTRIES: usize = 200;
-pub const JUPITER_BASE_URL: &str = "https://quote-api.jup.ag/v6";
-pub const JUPITER_USER_PUBKEY: &str = "11111111111111111111111111111111";
-pub const DEFAULT_SLIPPAGE_BPS: u16 = 50;
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+const DEFAULT_BASE_TOKEN: &str = "SOL";
+const DEFAULT_QUOTE_TOKEN: &str = "USDC";
 
-pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
-pub const SOL_DECIMALS: u8 = 9;
-pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeMqejdX3tqZZzcny9qE8P4AQV7B7";
-pub const USDC_DECIMALS: u8 = 6;
+/// Command-line flags. Each flag can also be supplied through the environment
+/// variable listed next to it; both take precedence over the TOML file. Only
+/// the keys below can be overridden: fees, impact, latency, tokens, feeds,
+/// strategies and the rest of the file have no flag.
+#[derive(Debug, Parser)]
+#[command(about = "Token strategy simulator driven by Pyth Hermes prices")]
+pub struct Cli {
+    /// Path to the TOML configuration file.
+    #[arg(long, env = "CONFIG_PATH")]
+    pub config: Option<PathBuf>,
 
-#[derive(Clone, Copy, Debug)]
-pub enum PriceFeed {
-    SolUsd,
+    /// Address the dashboard listens on.
+    #[arg(long, env = "BIND_ADDR")]
+    pub bind: Option<String>,
+
+    /// Use live Jupiter quotes instead of local pricing.
+    #[arg(long, env = "ENABLE_JUPITER", value_parser = BoolishValueParser::new(), action = ArgAction::Set)]
+    pub jupiter: Option<bool>,
+
+    /// Base URL of the Jupiter quote API.
+    #[arg(long, env = "JUPITER_BASE_URL")]
+    pub jupiter_url: Option<String>,
+
+    /// Slippage tolerance sent with Jupiter quotes, in basis points.
+    #[arg(long, env = "SLIPPAGE_BPS")]
+    pub slippage_bps: Option<u16>,
+
+    /// Number of swap records kept in memory.
+    #[arg(long, env = "MAX_HISTORY_ENTRIES")]
+    pub max_history: Option<usize>,
+
+    /// Drive the server from the synthetic market instead of Hermes.
+    #[arg(long, env = "SYNTHETIC_MARKET", value_parser = BoolishValueParser::new(), action = ArgAction::Set)]
+    pub synthetic: Option<bool>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Offline runs; without one the dashboard is served against live Hermes.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Replay a price history through the configured strategies.
+    Backtest(BacktestArgs),
+    /// Rank parameter sets of one strategy by backtesting each of them.
+    Sweep(SweepArgs),
+    /// Refit a strategy on rolling in-sample windows and test each fit on the
+    /// window that follows.
+    WalkForward(WalkForwardArgs),
+    /// Backtest resampled histories to see how much of each strategy's
+    /// result is luck.
+    MonteCarlo(MonteCarloArgs),
+}
+
+/// Price history an offline run replays.
+#[derive(Debug, Args)]
+pub struct HistoryArgs {
+    /// CSV or JSONL file of `timestamp`, `price` and optional `feed` rows.
+    pub prices: PathBuf,
+
+    /// Feed of rows that do not name one.
+    #[arg(long, default_value = "SOL/USD")]
+    pub feed: String,
+}
+
+#[derive(Debug, Args)]
+pub struct BacktestArgs {
+    #[command(flatten)]
+    pub history: HistoryArgs,
+
+    /// Write the JSON report to this file instead of stdout.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct SweepArgs {
+    #[command(flatten)]
+    pub history: HistoryArgs,
+
+    /// TOML file naming the strategy, objective and parameter space.
+    #[arg(long)]
+    pub spec: PathBuf,
+
+    /// Number of ranked parameter sets printed.
+    #[arg(long, default_value_t = 20)]
+    pub top: usize,
+
+    /// Write every trial as JSON to this file.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct WalkForwardArgs {
+    #[command(flatten)]
+    pub history: HistoryArgs,
+
+    /// TOML file naming the strategy, objective and parameter space.
+    #[arg(long)]
+    pub spec: PathBuf,
+
+    /// Length of each fitting window, e.g. `3600`, `90m`, `6h` or `2d`.
+    #[arg(long, value_parser = parse_duration)]
+    pub in_sample: TimeDelta,
+
+    /// Length of each test window; windows advance by this much.
+    #[arg(long, value_parser = parse_duration)]
+    pub out_of_sample: TimeDelta,
+
+    /// How much of the end of each fitting window is replayed, without
+    /// trading, to prime indicators before the test window.
+    #[arg(long, value_parser = parse_duration, default_value = "1h")]
+    pub warmup: TimeDelta,
+
+    /// Grow fitting windows from the start of the history instead of rolling
+    /// them forward.
+    #[arg(long)]
+    pub anchored: bool,
+
+    /// Write the windows and stitched equity curve as JSON to this file.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct MonteCarloArgs {
+    #[command(flatten)]
+    pub history: HistoryArgs,
+
+    /// What is resampled: the tick-to-tick price returns, replayed through
+    /// every strategy, or the equity change between each strategy's trades.
+    #[arg(long, value_enum, default_value_t = Resample::Prices)]
+    pub resample: Resample,
+
+    /// Number of resampled histories.
+    #[arg(long, default_value_t = 1000, value_parser = clap::value_parser!(u32).range(1..))]
+    pub runs: u32,
+
+    /// Consecutive returns drawn together, preserving short-range
+    /// dependence; 1 draws them independently.
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub block: u32,
+
+    /// Seed for reproducible draws; random when unset.
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Write the distributions as JSON to this file.
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resample {
+    Prices,
+    Trades,
+}
+
+/// Parses seconds, optionally suffixed with `s`, `m`, `h` or `d`.
+fn parse_duration(value: &str) -> Result<TimeDelta, String> {
+    let (number, unit_secs) = match value.char_indices().last() {
+        Some((index, 's')) => (&value[..index], 1),
+        Some((index, 'm')) => (&value[..index], 60),
+        Some((index, 'h')) => (&value[..index], 3_600),
+        Some((index, 'd')) => (&value[..index], 86_400),
+        _ => (value, 1),
+    };
+    number
+        .parse::<i64>()
+        .ok()
+        .filter(|number| *number > 0)
+        .and_then(|number| number.checked_mul(unit_secs))
+        .and_then(TimeDelta::try_seconds)
+        .ok_or_else(|| format!("expected a positive duration like `90m` or `6h`, got `{value}`"))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub hermes: HermesConfig,
+    pub jupiter: JupiterConfig,
+    pub simulation: SimulationConfig,
+    pub fees: FeeConfig,
+    pub impact: ImpactConfig,
+    pub latency: LatencyConfig,
+    pub synthetic: SyntheticConfig,
+    pub tokens: Vec<TokenConfig>,
+    pub strategies: Vec<StrategyConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_addr: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HermesConfig {
+    pub stream_base: String,
+    pub reconnect_delay_secs: u64,
+    /// Feeds requested over the single Hermes SSE connection.
+    pub feeds: Vec<PriceFeed>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JupiterConfig {
+    pub enabled: bool,
+    pub base_url: String,
+    pub user_pubkey: String,
+    pub slippage_bps: u16,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimulationConfig {
+    pub max_history_entries: usize,
+    /// How often time-based strategy hooks are evaluated.
+    pub scheduler_tick_secs: u64,
+    /// Seed for the simulation's random draws; unset draws a fresh one.
+    pub seed: Option<u64>,
+}
+
+/// Costs charged on every simulated swap. Network fees are debited from the
+/// strategy's SOL balance, or waived if it cannot cover them and the swap
+/// does not buy SOL; DEX and platform fees are withheld from output.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeeConfig {
+    pub base_fee_lamports: u64,
+    /// Priority fee for locally priced swaps; Jupiter simulations report
+    /// their own.
+    pub priority_fee_lamports: u64,
+    pub jito_tip_lamports: u64,
+    /// Pool fee for locally priced swaps; Jupiter quotes already include it.
+    pub dex_fee_bps: u16,
+    pub platform_fee_bps: u16,
+}
+
+/// Price impact charged on locally priced swaps, as a fraction of the
+/// oracle-priced output.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ImpactConfig {
+    /// Fill at the oracle price.
+    #[default]
+    None,
+    /// Trade against a constant-product pool holding `liquidity_usd` on each
+    /// side, or the depth listed for the pair in `pools` (keyed `"SOL/USDC"`).
+    ConstantProduct {
+        liquidity_usd: f64,
+        #[serde(default)]
+        pools: BTreeMap<String, f64>,
+    },
+    /// Fit `impact = k * notional_usd^alpha` per pair to the impact of recent
+    /// Jupiter quotes, from live swaps and from probes at `probe_sizes_usd`.
+    Fitted {
+        #[serde(default = "default_impact_samples")]
+        max_samples: usize,
+        #[serde(default)]
+        probe_sizes_usd: Vec<f64>,
+        #[serde(default = "default_probe_interval_secs")]
+        probe_interval_secs: u64,
+        /// Constant-product depth used until a pair has a fit.
+        #[serde(default)]
+        fallback_liquidity_usd: Option<f64>,
+    },
+}
+
+/// Delay between a strategy's decision and its fill. A delayed swap fills at
+/// the first price observed once the delay has passed.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case", deny_unknown_fields)]
+pub enum LatencyConfig {
+    /// Fill on the tick the decision was made on.
+    #[default]
+    None,
+    Fixed {
+        millis: u64,
+    },
+    Uniform {
+        min_millis: u64,
+        max_millis: u64,
+    },
+    /// Log-normal delay with the given median; `sigma` is the standard
+    /// deviation of its logarithm.
+    LogNormal {
+        median_millis: f64,
+        sigma: f64,
+    },
+}
+
+/// Generated prices that replace the Hermes stream when enabled, so the
+/// server runs offline. Feeds are keyed by their `hermes.feeds` name; feeds
+/// not listed here receive no prices.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyntheticConfig {
+    pub enabled: bool,
+    pub tick_millis: u64,
+    /// Seed for the generated paths; unset draws a fresh one.
+    pub seed: Option<u64>,
+    pub feeds: BTreeMap<String, SyntheticFeedConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SyntheticFeedConfig {
+    pub initial_price: f64,
+    #[serde(default)]
+    pub process: PriceProcess,
+    /// Scripted falls applied on top of the process.
+    #[serde(default)]
+    pub crashes: Vec<CrashConfig>,
+}
+
+/// Stochastic process followed by a synthetic price. Rates are annualized.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PriceProcess {
+    /// Price moved only by crashes.
+    #[default]
+    Constant,
+    /// Geometric Brownian motion.
+    Gbm {
+        #[serde(default)]
+        drift: f64,
+        volatility: f64,
+    },
+    /// Geometric Brownian motion plus jumps arriving `jumps_per_year` times a
+    /// year, each moving the log price by a normal draw. The diffusion drift
+    /// is compensated for the average jump, so `drift` stays the expected
+    /// return.
+    JumpDiffusion {
+        #[serde(default)]
+        drift: f64,
+        volatility: f64,
+        jumps_per_year: f64,
+        #[serde(default)]
+        jump_mean: f64,
+        jump_volatility: f64,
+    },
+    /// Ornstein–Uhlenbeck log price pulled back towards `mean` at rate
+    /// `reversion`.
+    OrnsteinUhlenbeck {
+        mean: f64,
+        reversion: f64,
+        volatility: f64,
+    },
+}
+
+/// Fall by `drop`, as a fraction, starting `at_secs` after startup and
+/// spread over `fall_secs`, then a recovery of `recovery` of the lost
+/// ground over `recovery_secs`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CrashConfig {
+    pub at_secs: u64,
+    pub drop: f64,
+    #[serde(default)]
+    pub fall_secs: u64,
+    #[serde(default)]
+    pub recovery: f64,
+    #[serde(default)]
+    pub recovery_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenConfig {
+    pub symbol: String,
+    pub mint: String,
+    pub decimals: u8,
+    /// Name of the Hermes feed pricing this token in USD. Tokens without a
+    /// feed are treated as USD stablecoins.
+    #[serde(default)]
+    pub feed: Option<String>,
+    #[serde(default)]
+    pub min_trade_amount: f64,
+}
+
+/// One strategy instance. `kind` selects the implementation registered in
+/// `strategies::StrategyRegistry`; `params` is handed to it unparsed.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StrategyConfig {
+    pub kind: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Symbol of the traded token; its Hermes feed drives the strategy.
+    #[serde(default = "default_base_token")]
+    pub base: String,
+    #[serde(default = "default_quote_token")]
+    pub quote: String,
+    /// Starting wallet, keyed by token symbol.
+    #[serde(default = "default_initial_balances")]
+    pub initial_balances: BTreeMap<String, f64>,
+    #[serde(default)]
+    pub params: toml::Table,
+    /// Overrides the trade sizes chosen by the strategy, if set.
+    #[serde(default)]
+    pub sizing: Option<SizingConfig>,
+    /// Limits that halt the strategy when breached, if set.
+    #[serde(default)]
+    pub risk: Option<RiskConfig>,
+    /// Protective orders placed after every fill that buys base, if set.
+    #[serde(default)]
+    pub exits: Option<ExitConfig>,
+    /// Orders resting in the strategy's book at startup.
+    #[serde(default)]
+    pub orders: Vec<Order>,
 }
 
-pub fn jupiter_enabled() -> bool {
-    match env::var("ENABLE_JUPITER") {
-        Ok(value) => {
-            let normalized = value.trim().to_lowercase();
-            matches!(normalized.as_str(), "1" | "true" | "yes" | "on")
+/// How a strategy's trades are sized. Every mode except `fixed` sizes a
+/// trade as a notional in quote, relative to the pair's equity in quote.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case", deny_unknown_fields)]
+pub enum SizingConfig {
+    /// Sells `base_amount` of base or spends `quote_amount` of quote.
+    Fixed { base_amount: f64, quote_amount: f64 },
+    /// Trades `fraction` of equity.
+    PercentOfEquity { fraction: f64 },
+    /// Trades the share of equity that would run at `target_vol` annualized
+    /// volatility given the realized volatility, capped at `max_fraction`.
+    VolTarget {
+        target_vol: f64,
+        #[serde(default = "default_vol_window")]
+        vol_window: usize,
+        #[serde(default = "default_max_fraction")]
+        max_fraction: f64,
+    },
+    /// Trades `kelly_fraction` of the continuous Kelly share
+    /// `expected_return / realized_vol^2`, capped at `max_fraction`.
+    Kelly {
+        kelly_fraction: f64,
+        /// Expected annualized return of the strategy.
+        expected_return: f64,
+        #[serde(default = "default_vol_window")]
+        vol_window: usize,
+        #[serde(default = "default_max_fraction")]
+        max_fraction: f64,
+    },
+}
+
+/// Per-strategy risk limits. Equity is the value of the strategy's wallet in
+/// its quote token; unset limits are not enforced.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RiskConfig {
+    /// Largest fall from peak equity, as a fraction, before the strategy is
+    /// halted for good.
+    pub max_drawdown: Option<f64>,
+    /// Largest fall from the equity at the start of the UTC day, as a
+    /// fraction, before the strategy is halted for the rest of the day.
+    pub max_daily_loss: Option<f64>,
+    /// Filled trades allowed in any rolling hour; further trades halt the
+    /// strategy until the oldest one leaves the window.
+    pub max_trades_per_hour: Option<usize>,
+    /// Largest trade, in quote, the strategy may place; a larger one is
+    /// rejected and the strategy keeps running.
+    pub max_notional_per_trade: Option<f64>,
+}
+
+/// Exit orders attached to each purchase of base, as one-cancels-other
+/// group. Offsets are fractions of the fill price.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExitConfig {
+    pub stop_loss: Option<f64>,
+    pub take_profit: Option<f64>,
+    pub trailing_stop: Option<f64>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: "0.0.0.0:3001".to_string(),
         }
-        Err(_) => false,
     }
 }
 
-impl PriceFeed {
-    pub fn id(self) -> &'static str {
-        match self {
-            PriceFeed::SolUsd => {
-                "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d"
+impl Default for HermesConfig {
+    fn default() -> Self {
+        Self {
+            stream_base: "https://hermes.pyth.network/v2/updates/price/stream".to_string(),
+            reconnect_delay_secs: 3,
+            feeds: vec![PriceFeed {
+                name: "SOL/USD".to_string(),
+                id: "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d"
+                    .to_string(),
+            }],
+        }
+    }
+}
+
+impl Default for JupiterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_url: "https://quote-api.jup.ag/v6".to_string(),
+            user_pubkey: "11111111111111111111111111111111".to_string(),
+            slippage_bps: 50,
+        }
+    }
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            max_history_entries: 200,
+            scheduler_tick_secs: 1,
+            seed: None,
+        }
+    }
+}
+
+impl Default for SyntheticConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tick_millis: 1_000,
+            seed: None,
+            feeds: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            base_fee_lamports: 5_000,
+            priority_fee_lamports: 0,
+            jito_tip_lamports: 0,
+            dex_fee_bps: 25,
+            platform_fee_bps: 0,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            server: ServerConfig::default(),
+            hermes: HermesConfig::default(),
+            jupiter: JupiterConfig::default(),
+            simulation: SimulationConfig::default(),
+            fees: FeeConfig::default(),
+            impact: ImpactConfig::default(),
+            latency: LatencyConfig::default(),
+            synthetic: SyntheticConfig::default(),
+            tokens: vec![
+                TokenConfig {
+                    symbol: "SOL".to_string(),
+                    mint: "So11111111111111111111111111111111111111112".to_string(),
+                    decimals: 9,
+                    feed: Some("SOL/USD".to_string()),
+                    min_trade_amount: 1e-6,
+                },
+                TokenConfig {
+                    symbol: "USDC".to_string(),
+                    mint: "EPjFWdd5AufqSSqeMqejdX3tqZZzcny9qE8P4AQV7B7".to_string(),
+                    decimals: 6,
+                    feed: None,
+                    min_trade_amount: 0.01,
+                },
+            ],
+            strategies: vec![
+                StrategyConfig::builtin("alternating"),
+                StrategyConfig::builtin("trend_follow"),
+                StrategyConfig::builtin("range_trader"),
+            ],
+        }
+    }
+}
+
+impl StrategyConfig {
+    fn builtin(kind: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            label: None,
+            base: default_base_token(),
+            quote: default_quote_token(),
+            initial_balances: default_initial_balances(),
+            params: toml::Table::new(),
+            sizing: None,
+            risk: None,
+            exits: None,
+            orders: Vec::new(),
+        }
+    }
+}
+
+impl SizingConfig {
+    fn validate(&self, section: &str) -> Result<()> {
+        let ensure_fraction = |field: &str, value: f64| {
+            if value > 0.0 && value <= 1.0 {
+                Ok(())
+            } else {
+                Err(anyhow!("{section}.{field} must be in (0, 1], got {value}"))
+            }
+        };
+        let ensure_window = |window: usize| {
+            if window >= 2 {
+                Ok(())
+            } else {
+                Err(anyhow!("{section}.vol_window must be at least 2"))
+            }
+        };
+        match *self {
+            SizingConfig::Fixed {
+                base_amount,
+                quote_amount,
+            } => {
+                if base_amount <= 0.0 || quote_amount <= 0.0 {
+                    bail!("{section} amounts must be positive");
+                }
+            }
+            SizingConfig::PercentOfEquity { fraction } => ensure_fraction("fraction", fraction)?,
+            SizingConfig::VolTarget {
+                target_vol,
+                vol_window,
+                max_fraction,
+            } => {
+                if target_vol <= 0.0 {
+                    bail!("{section}.target_vol must be positive, got {target_vol}");
+                }
+                ensure_window(vol_window)?;
+                ensure_fraction("max_fraction", max_fraction)?;
             }
+            SizingConfig::Kelly {
+                kelly_fraction,
+                expected_return: _,
+                vol_window,
+                max_fraction,
+            } => {
+                ensure_fraction("kelly_fraction", kelly_fraction)?;
+                ensure_window(vol_window)?;
+                ensure_fraction("max_fraction", max_fraction)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RiskConfig {
+    fn validate(&self, section: &str) -> Result<()> {
+        for (field, value) in [
+            ("max_drawdown", self.max_drawdown),
+            ("max_daily_loss", self.max_daily_loss),
+        ] {
+            if let Some(value) = value
+                && !(value > 0.0 && value < 1.0)
+            {
+                bail!("{section}.{field} must be between 0 and 1, got {value}");
+            }
+        }
+        if self.max_trades_per_hour == Some(0) {
+            bail!("{section}.max_trades_per_hour must be greater than zero");
+        }
+        if let Some(notional) = self.max_notional_per_trade
+            && notional <= 0.0
+        {
+            bail!("{section}.max_notional_per_trade must be positive, got {notional}");
+        }
+        Ok(())
+    }
+}
+
+impl ExitConfig {
+    fn validate(&self, section: &str) -> Result<()> {
+        for (field, value) in [
+            ("stop_loss", self.stop_loss),
+            ("trailing_stop", self.trailing_stop),
+        ] {
+            if let Some(value) = value
+                && !(value > 0.0 && value < 1.0)
+            {
+                bail!("{section}.{field} must be between 0 and 1, got {value}");
+            }
+        }
+        if let Some(value) = self.take_profit
+            && value <= 0.0
+        {
+            bail!("{section}.take_profit must be positive, got {value}");
         }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Builds the configuration from defaults, the TOML file, environment
+    /// variables and command-line flags, in increasing order of precedence.
+    pub fn from_cli(cli: &Cli) -> Result<Self> {
+        let mut config = match &cli.config {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?
+            }
+            None => Self::default(),
+        };
+        config.apply_overrides(cli);
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("failed to parse config file {}", path.display()))
+    }
+
+    fn apply_overrides(&mut self, cli: &Cli) {
+        if let Some(bind) = &cli.bind {
+            self.server.bind_addr = bind.clone();
+        }
+        if let Some(enabled) = cli.jupiter {
+            self.jupiter.enabled = enabled;
+        }
+        if let Some(url) = &cli.jupiter_url {
+            self.jupiter.base_url = url.clone();
+        }
+        if let Some(bps) = cli.slippage_bps {
+            self.jupiter.slippage_bps = bps;
+        }
+        if let Some(max_history) = cli.max_history {
+            self.simulation.max_history_entries = max_history;
+        }
+        if let Some(enabled) = cli.synthetic {
+            self.synthetic.enabled = enabled;
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.server
+            .bind_addr
+            .parse::<SocketAddr>()
+            .with_context(|| format!("server.bind_addr `{}` is invalid", self.server.bind_addr))?;
+
+        ensure_http_url("hermes.stream_base", &self.hermes.stream_base)?;
+        self.hermes.validate()?;
+        ensure_http_url("jupiter.base_url", &self.jupiter.base_url)?;
+
+        if self.jupiter.slippage_bps > 10_000 {
+            bail!(
+                "jupiter.slippage_bps must be at most 10000, got {}",
+                self.jupiter.slippage_bps
+            );
+        }
+        if self.simulation.max_history_entries == 0 {
+            bail!("simulation.max_history_entries must be greater than zero");
+        }
+        for (field, bps) in [
+            ("dex_fee_bps", self.fees.dex_fee_bps),
+            ("platform_fee_bps", self.fees.platform_fee_bps),
+        ] {
+            if bps > 10_000 {
+                bail!("fees.{field} must be at most 10000, got {bps}");
+            }
+        }
+        if u32::from(self.fees.dex_fee_bps) + u32::from(self.fees.platform_fee_bps) > 10_000 {
+            bail!("fees.dex_fee_bps and fees.platform_fee_bps must add up to at most 10000");
+        }
+        self.validate_impact()?;
+        match self.latency {
+            LatencyConfig::Uniform {
+                min_millis,
+                max_millis,
+            } if min_millis > max_millis => {
+                bail!("latency.min_millis must not exceed latency.max_millis");
+            }
+            LatencyConfig::LogNormal {
+                median_millis,
+                sigma,
+            } if median_millis <= 0.0 || sigma < 0.0 => {
+                bail!("latency.median_millis must be positive and latency.sigma non-negative");
+            }
+            _ => {}
+        }
+        if self.simulation.scheduler_tick_secs == 0 {
+            bail!("simulation.scheduler_tick_secs must be greater than zero");
+        }
+        self.validate_synthetic()?;
+
+        self.validate_tokens()?;
+        if self.strategies.is_empty() {
+            bail!("at least one [[strategies]] entry is required");
+        }
+        for (index, strategy) in self.strategies.iter().enumerate() {
+            let section = format!("strategies[{index}]");
+            if strategy.kind.trim().is_empty() {
+                bail!("{section}.kind must not be empty");
+            }
+            self.validate_pair(&section, &strategy.base, &strategy.quote)?;
+            self.validate_balances(&section, &strategy.initial_balances)?;
+            if let Some(sizing) = &strategy.sizing {
+                sizing.validate(&format!("{section}.sizing"))?;
+            }
+            if let Some(risk) = &strategy.risk {
+                risk.validate(&format!("{section}.risk"))?;
+            }
+            if let Some(exits) = &strategy.exits {
+                exits.validate(&format!("{section}.exits"))?;
+            }
+            for (order_index, order) in strategy.orders.iter().enumerate() {
+                order.validate(&format!("{section}.orders[{order_index}]"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_synthetic(&self) -> Result<()> {
+        let synthetic = &self.synthetic;
+        if synthetic.tick_millis == 0 {
+            bail!("synthetic.tick_millis must be greater than zero");
+        }
+        if synthetic.enabled && synthetic.feeds.is_empty() {
+            bail!("synthetic.feeds must list at least one feed when synthetic.enabled is set");
+        }
+        for (name, feed) in &synthetic.feeds {
+            let section = format!("synthetic.feeds.\"{name}\"");
+            self.hermes.feed_id(&section, name)?;
+            if !(feed.initial_price.is_finite() && feed.initial_price > 0.0) {
+                bail!("{section}.initial_price must be positive");
+            }
+            let finite = |values: &[f64]| values.iter().all(|value| value.is_finite());
+            match feed.process {
+                PriceProcess::Constant => {}
+                PriceProcess::Gbm { drift, volatility } => {
+                    if !finite(&[drift, volatility]) || volatility < 0.0 {
+                        bail!("{section}.process needs a finite drift and non-negative volatility");
+                    }
+                }
+                PriceProcess::JumpDiffusion {
+                    drift,
+                    volatility,
+                    jumps_per_year,
+                    jump_mean,
+                    jump_volatility,
+                } => {
+                    if !finite(&[
+                        drift,
+                        volatility,
+                        jumps_per_year,
+                        jump_mean,
+                        jump_volatility,
+                    ]) || volatility < 0.0
+                        || jumps_per_year < 0.0
+                        || jump_volatility < 0.0
+                    {
+                        bail!(
+                            "{section}.process needs finite values and non-negative volatility, jumps_per_year and jump_volatility"
+                        );
+                    }
+                }
+                PriceProcess::OrnsteinUhlenbeck {
+                    mean,
+                    reversion,
+                    volatility,
+                } => {
+                    if !finite(&[mean, reversion, volatility])
+                        || mean <= 0.0
+                        || reversion < 0.0
+                        || volatility < 0.0
+                    {
+                        bail!(
+                            "{section}.process needs a positive mean and non-negative reversion and volatility"
+                        );
+                    }
+                }
+            }
+            for (index, crash) in feed.crashes.iter().enumerate() {
+                if !(crash.drop > 0.0 && crash.drop < 1.0) {
+                    bail!("{section}.crashes[{index}].drop must be in (0, 1)");
+                }
+                if !(0.0..=1.0).contains(&crash.recovery) {
+                    bail!("{section}.crashes[{index}].recovery must be in [0, 1]");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_tokens(&self) -> Result<()> {
+        let mut symbols = HashSet::new();
+        let mut mints = HashSet::new();
+        for token in &self.tokens {
+            if !symbols.insert(token.symbol.as_str()) {
+                bail!("token `{}` is listed more than once", token.symbol);
+            }
+            if !mints.insert(token.mint.as_str()) {
+                bail!("token `{}` reuses mint {}", token.symbol, token.mint);
+            }
+            if token.decimals > 18 {
+                bail!(
+                    "token `{}` has unsupported decimals {}",
+                    token.symbol,
+                    token.decimals
+                );
+            }
+            if token.min_trade_amount < 0.0 {
+                bail!(
+                    "token `{}` min_trade_amount must not be negative",
+                    token.symbol
+                );
+            }
+            if let Some(feed) = &token.feed {
+                self.hermes
+                    .feed_id(&format!("tokens.{}.feed", token.symbol), feed)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_impact(&self) -> Result<()> {
+        match &self.impact {
+            ImpactConfig::None => {}
+            ImpactConfig::ConstantProduct {
+                liquidity_usd,
+                pools,
+            } => {
+                if *liquidity_usd <= 0.0 {
+                    bail!("impact.liquidity_usd must be positive, got {liquidity_usd}");
+                }
+                for (pair, depth) in pools {
+                    let section = format!("impact.pools.\"{pair}\"");
+                    let Some((base, quote)) = pair.split_once('/') else {
+                        bail!("{section} must be named `BASE/QUOTE`");
+                    };
+                    for symbol in [base, quote] {
+                        if !self.tokens.iter().any(|token| token.symbol == symbol) {
+                            bail!("{section} refers to unknown token `{symbol}`");
+                        }
+                    }
+                    if *depth <= 0.0 {
+                        bail!("{section} must be positive, got {depth}");
+                    }
+                }
+            }
+            ImpactConfig::Fitted {
+                max_samples,
+                probe_sizes_usd,
+                probe_interval_secs,
+                fallback_liquidity_usd,
+            } => {
+                if *max_samples < 2 {
+                    bail!("impact.max_samples must be at least 2");
+                }
+                if probe_sizes_usd.iter().any(|size| *size <= 0.0) {
+                    bail!("impact.probe_sizes_usd must all be positive");
+                }
+                if *probe_interval_secs == 0 {
+                    bail!("impact.probe_interval_secs must be greater than zero");
+                }
+                if let Some(depth) = fallback_liquidity_usd
+                    && *depth <= 0.0
+                {
+                    bail!("impact.fallback_liquidity_usd must be positive, got {depth}");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_pair(&self, section: &str, base: &str, quote: &str) -> Result<()> {
+        let find = |symbol: &str| {
+            self.tokens
+                .iter()
+                .find(|token| token.symbol == symbol)
+                .ok_or_else(|| anyhow!("{section} refers to unknown token `{symbol}`"))
+        };
+        let base_token = find(base)?;
+        find(quote)?;
+        if base == quote {
+            bail!("{section} must trade two different tokens");
+        }
+        if base_token.feed.is_none() {
+            bail!("{section} base token `{base}` needs a Hermes feed to drive the strategy");
+        }
+        Ok(())
+    }
+
+    fn validate_balances(&self, section: &str, balances: &BTreeMap<String, f64>) -> Result<()> {
+        for (symbol, amount) in balances {
+            if !self.tokens.iter().any(|token| &token.symbol == symbol) {
+                bail!("{section}.initial_balances refers to unknown token `{symbol}`");
+            }
+            if !(amount.is_finite() && *amount >= 0.0) {
+                bail!("{section}.initial_balances.{symbol} must be a non-negative amount");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl HermesConfig {
+    /// Resolves a feed name used elsewhere in the config to its normalized id.
+    pub fn feed_id(&self, field: &str, name: &str) -> Result<String> {
+        self.feeds
+            .iter()
+            .find(|feed| feed.name == name)
+            .map(PriceFeed::normalized_id)
+            .ok_or_else(|| anyhow!("{field} refers to unknown feed `{name}`"))
+    }
+
+    pub fn stream_url(&self) -> String {
+        let ids = self
+            .feeds
+            .iter()
+            .map(|feed| format!("ids[]={}", feed.id))
+            .collect::<Vec<_>>()
+            .join("&");
+        format!("{}?{ids}", self.stream_base)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.feeds.is_empty() {
+            bail!("hermes.feeds must list at least one feed");
+        }
+        let mut seen = HashSet::new();
+        for feed in &self.feeds {
+            let id = feed.normalized_id();
+            if id.len() != 64 || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+                bail!(
+                    "hermes feed `{}` has an invalid id `{}`",
+                    feed.name,
+                    feed.id
+                );
+            }
+            if !seen.insert(id) {
+                bail!("hermes feed `{}` is listed more than once", feed.name);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn ensure_http_url(field: &str, value: &str) -> Result<()> {
+    if !(value.starts_with("http://") || value.starts_with("https://")) {
+        bail!("{field} must be an http(s) URL, got `{value}`");
+    }
+    Ok(())
+}
+
+fn default_base_token() -> String {
+    DEFAULT_BASE_TOKEN.to_string()
+}
+
+fn default_quote_token() -> String {
+    DEFAULT_QUOTE_TOKEN.to_string()
+}
+
+fn default_initial_balances() -> BTreeMap<String, f64> {
+    BTreeMap::from([(DEFAULT_BASE_TOKEN.to_string(), 1.0)])
+}
+
+fn default_impact_samples() -> usize {
+    50
+}
+
+fn default_probe_interval_secs() -> u64 {
+    300
+}
+
+fn default_vol_window() -> usize {
+    100
+}
+
+fn default_max_fraction() -> f64 {
+    1.0
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PriceFeed {
+    pub name: String,
+    pub id: String,
+}
+
+impl PriceFeed {
+    /// Hermes reports ids in lowercase hex without the `0x` prefix.
+    pub fn normalized_id(&self) -> String {
+        normalize_feed_id(&self.id)
+    }
+}
+
+pub fn normalize_feed_id(id: &str) -> String {
+    id.trim_start_matches("0x").to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Config {
+        toml::from_str(contents).unwrap()
+    }
+
+    fn error(contents: &str) -> String {
+        parse(contents).validate().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn shipped_config_and_defaults_validate() {
+        Config::from_file(Path::new(DEFAULT_CONFIG_PATH))
+            .unwrap()
+            .validate()
+            .unwrap();
+        Config::default().validate().unwrap();
+    }
+
+    #[test]
+    fn shipped_fees_match_the_defaults() {
+        let shipped = Config::from_file(Path::new(DEFAULT_CONFIG_PATH))
+            .unwrap()
+            .fees;
+        let default = FeeConfig::default();
+        assert_eq!(shipped.base_fee_lamports, default.base_fee_lamports);
+        assert_eq!(shipped.priority_fee_lamports, default.priority_fee_lamports);
+        assert_eq!(shipped.jito_tip_lamports, default.jito_tip_lamports);
+        assert_eq!(shipped.dex_fee_bps, default.dex_fee_bps);
+        assert_eq!(shipped.platform_fee_bps, default.platform_fee_bps);
+    }
+
+    #[test]
+    fn fee_bps_must_leave_some_output() {
+        let message = error("fees = { dex_fee_bps = 6000, platform_fee_bps = 5000 }");
+        assert!(message.contains("add up to at most 10000"), "{message}");
+    }
+
+    #[test]
+    fn strategies_must_trade_known_distinct_tokens() {
+        let message = error(
+            r#"
+            [[strategies]]
+            kind = "alternating"
+            base = "SOL"
+            quote = "BONK"
+            "#,
+        );
+        assert!(message.contains("unknown token `BONK`"), "{message}");
+        let message = error(
+            r#"
+            [[strategies]]
+            kind = "alternating"
+            base = "SOL"
+            quote = "SOL"
+            "#,
+        );
+        assert!(message.contains("two different tokens"), "{message}");
+    }
+
+    #[test]
+    fn base_token_needs_a_feed() {
+        let message = error(
+            r#"
+            [[strategies]]
+            kind = "alternating"
+            base = "USDC"
+            quote = "SOL"
+            "#,
+        );
+        assert!(message.contains("needs a Hermes feed"), "{message}");
     }
 
-    pub fn stream_url(self) -> String {
-        format!("{HERMES_STREAM_BASE}?ids[]={}", self.id())
+    #[test]
+    fn flags_override_the_file() {
+        let cli = Cli::parse_from([
+            "simulator",
+            "--bind",
+            "127.0.0.1:9000",
+            "--slippage-bps",
+            "75",
+            "--max-history",
+            "10",
+        ]);
+        let mut config = Config::default();
+        config.apply_overrides(&cli);
+        config.validate().unwrap();
+        assert_eq!(config.server.bind_addr, "127.0.0.1:9000");
+        assert_eq!(config.jupiter.slippage_bps, 75);
+        assert_eq!(config.simulation.max_history_entries, 10);
     }
 }
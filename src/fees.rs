@@ -0,0 +1,177 @@
+use crate::config::FeeConfig;
+use crate::jupiter::JupiterSimulation;
+use crate::tokens::{Token, TokenRegistry};
+use serde::Serialize;
+
+/// Costs charged on one swap. Network costs are paid in SOL; DEX and
+/// platform fees are withheld from the swap's output.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FeeBreakdown {
+    pub base_fee_lamports: u64,
+    pub priority_fee_lamports: u64,
+    pub jito_tip_lamports: u64,
+    /// Pool fee withheld from the output, in output base units. Jupiter
+    /// quotes are already net of it, so it is only charged on local fills.
+    pub dex_fee: u64,
+    /// Platform fee withheld from the output, in output base units.
+    pub platform_fee: u64,
+}
+
+impl FeeBreakdown {
+    pub fn network_lamports(&self) -> u64 {
+        self.base_fee_lamports + self.priority_fee_lamports + self.jito_tip_lamports
+    }
+
+    /// Drops the network fees, for a wallet without the SOL to pay them.
+    pub fn waive_network(&mut self) {
+        self.base_fee_lamports = 0;
+        self.priority_fee_lamports = 0;
+        self.jito_tip_lamports = 0;
+    }
+
+    /// Fees withheld from the output, in output base units.
+    pub fn output_fee(&self) -> u64 {
+        self.dex_fee + self.platform_fee
+    }
+}
+
+/// How a swap was priced, which decides where its network fees come from.
+pub enum Pricing {
+    Local,
+    /// Quoted by Jupiter; the simulation reports the transaction's own fees
+    /// when it succeeded.
+    Jupiter(Option<JupiterSimulation>),
+}
+
+#[derive(Debug, Clone)]
+pub struct FeeModel {
+    config: FeeConfig,
+    /// Token network fees are paid in. Swaps that do not buy it are only
+    /// charged if the wallet keeps enough of it to pay, so pairs without SOL
+    /// trade free of network fees.
+    sol: Option<Token>,
+}
+
+impl FeeModel {
+    pub fn new(config: &FeeConfig, tokens: &TokenRegistry) -> Self {
+        Self {
+            config: config.clone(),
+            sol: tokens.wrapped_sol().cloned(),
+        }
+    }
+
+    pub fn sol(&self) -> Option<&Token> {
+        self.sol.as_ref()
+    }
+
+    /// SOL a wallet selling SOL keeps back to pay the network fee of one swap
+    /// at the configured rates.
+    pub fn network_reserve(&self) -> Option<(&Token, u64)> {
+        let lamports = self.config.base_fee_lamports
+            + self.config.priority_fee_lamports
+            + self.config.jito_tip_lamports;
+        self.sol().map(|sol| (sol, lamports))
+    }
+
+    /// Fees for a swap with `gross_out` output base units before fees.
+    /// Output fees round down and never exceed `gross_out`.
+    pub fn charge(&self, gross_out: u64, pricing: &Pricing) -> FeeBreakdown {
+        let (base_fee_lamports, priority_fee_lamports, dex_fee_bps) = match pricing {
+            Pricing::Local => (
+                self.config.base_fee_lamports,
+                self.config.priority_fee_lamports,
+                self.config.dex_fee_bps,
+            ),
+            Pricing::Jupiter(Some(simulation)) => (
+                simulation.base_fee_lamports,
+                simulation.priority_fee_lamports,
+                0,
+            ),
+            Pricing::Jupiter(None) => (
+                self.config.base_fee_lamports,
+                self.config.priority_fee_lamports,
+                0,
+            ),
+        };
+        FeeBreakdown {
+            base_fee_lamports,
+            priority_fee_lamports,
+            jito_tip_lamports: self.config.jito_tip_lamports,
+            dex_fee: bps_of(gross_out, dex_fee_bps),
+            platform_fee: bps_of(gross_out, self.config.platform_fee_bps),
+        }
+    }
+}
+
+fn bps_of(units: u64, bps: u16) -> u64 {
+    (u128::from(units) * u128::from(bps) / 10_000) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    fn model(dex_fee_bps: u16, platform_fee_bps: u16) -> FeeModel {
+        let config = Config::default();
+        let tokens = TokenRegistry::from_config(&config).unwrap();
+        let fees = FeeConfig {
+            base_fee_lamports: 5_000,
+            priority_fee_lamports: 1_000,
+            jito_tip_lamports: 2_000,
+            dex_fee_bps,
+            platform_fee_bps,
+        };
+        FeeModel::new(&fees, &tokens)
+    }
+
+    #[test]
+    fn local_fills_pay_configured_network_and_output_fees() {
+        let fees = model(25, 10).charge(1_000_000, &Pricing::Local);
+        assert_eq!(fees.network_lamports(), 8_000);
+        assert_eq!(fees.dex_fee, 2_500);
+        assert_eq!(fees.platform_fee, 1_000);
+        assert_eq!(fees.output_fee(), 3_500);
+    }
+
+    #[test]
+    fn jupiter_fills_skip_the_pool_fee_and_use_simulated_network_fees() {
+        let model = model(25, 10);
+        let simulation = JupiterSimulation {
+            base_fee_lamports: 5_000,
+            priority_fee_lamports: 40_000,
+        };
+        let fees = model.charge(1_000_000, &Pricing::Jupiter(Some(simulation)));
+        assert_eq!(fees.dex_fee, 0);
+        assert_eq!(fees.platform_fee, 1_000);
+        assert_eq!(fees.network_lamports(), 47_000);
+
+        let fees = model.charge(1_000_000, &Pricing::Jupiter(None));
+        assert_eq!(fees.dex_fee, 0);
+        assert_eq!(fees.network_lamports(), 8_000);
+    }
+
+    #[test]
+    fn output_fees_round_down_and_stay_within_the_output() {
+        let fees = model(25, 0).charge(399, &Pricing::Local);
+        assert_eq!(fees.dex_fee, 0);
+        let fees = model(6_000, 4_000).charge(u64::MAX, &Pricing::Local);
+        assert_eq!(fees.output_fee(), u64::MAX);
+    }
+
+    #[test]
+    fn waiving_drops_network_fees_only() {
+        let mut fees = model(25, 10).charge(1_000_000, &Pricing::Local);
+        fees.waive_network();
+        assert_eq!(fees.network_lamports(), 0);
+        assert_eq!(fees.output_fee(), 3_500);
+    }
+
+    #[test]
+    fn network_reserve_is_one_swap_of_sol() {
+        let model = model(0, 0);
+        let (sol, lamports) = model.network_reserve().unwrap();
+        assert!(sol.is_wrapped_sol());
+        assert_eq!(lamports, 8_000);
+    }
+}
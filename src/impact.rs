@@ -0,0 +1,251 @@
+use crate::config::{Config, ImpactConfig};
+use crate::jupiter::JupiterClient;
+use crate::model::AppState;
+use crate::tokens::{Token, TradingPair};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{MissedTickBehavior, interval};
+
+/// Largest impact the model will charge, so fills never round to nothing.
+const MAX_IMPACT: f64 = 0.99;
+
+/// Price impact of locally priced swaps.
+#[derive(Debug)]
+pub struct ImpactModel {
+    config: ImpactConfig,
+    /// `(notional_usd, impact)` samples from Jupiter quotes, keyed by the
+    /// pair's mints in sorted order.
+    samples: HashMap<(String, String), VecDeque<(f64, f64)>>,
+}
+
+impl ImpactModel {
+    pub fn new(config: &ImpactConfig) -> Self {
+        Self {
+            config: config.clone(),
+            samples: HashMap::new(),
+        }
+    }
+
+    /// Fraction of the oracle-priced output lost to impact when swapping
+    /// `notional_usd` of `input` into `output`. `None` when the model charges
+    /// no impact at all.
+    pub fn impact(&self, input: &Token, output: &Token, notional_usd: f64) -> Option<f64> {
+        let impact = match &self.config {
+            ImpactConfig::None => return None,
+            ImpactConfig::ConstantProduct {
+                liquidity_usd,
+                pools,
+            } => {
+                let depth = pools
+                    .get(&format!("{}/{}", input.symbol, output.symbol))
+                    .or_else(|| pools.get(&format!("{}/{}", output.symbol, input.symbol)))
+                    .unwrap_or(liquidity_usd);
+                constant_product_impact(*depth, notional_usd)
+            }
+            ImpactConfig::Fitted {
+                fallback_liquidity_usd,
+                ..
+            } => match self.fit(input, output) {
+                Some((scale, exponent)) => scale * notional_usd.powf(exponent),
+                None => fallback_liquidity_usd
+                    .map(|depth| constant_product_impact(depth, notional_usd))
+                    .unwrap_or(0.0),
+            },
+        };
+        Some(impact.clamp(0.0, MAX_IMPACT))
+    }
+
+    /// Records the impact Jupiter quoted for a swap of `notional_usd`.
+    pub fn observe(&mut self, input: &Token, output: &Token, notional_usd: f64, impact: f64) {
+        let ImpactConfig::Fitted { max_samples, .. } = self.config else {
+            return;
+        };
+        if !(notional_usd > 0.0 && impact > 0.0) {
+            return;
+        }
+        let samples = self.samples.entry(pair_key(input, output)).or_default();
+        samples.push_back((notional_usd, impact));
+        if samples.len() > max_samples {
+            samples.pop_front();
+        }
+    }
+
+    /// Least-squares fit of `ln(impact) = ln(scale) + exponent * ln(notional)`.
+    fn fit(&self, input: &Token, output: &Token) -> Option<(f64, f64)> {
+        let samples = self.samples.get(&pair_key(input, output))?;
+        let points = samples
+            .iter()
+            .map(|(notional, impact)| (notional.ln(), impact.ln()))
+            .collect::<Vec<_>>();
+        let count = points.len() as f64;
+        if count < 2.0 {
+            return None;
+        }
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+        let covariance = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum::<f64>();
+        let variance = points
+            .iter()
+            .map(|(x, _)| (x - mean_x).powi(2))
+            .sum::<f64>();
+        if variance <= f64::EPSILON {
+            // All samples at one size: assume impact linear in size.
+            return Some((mean_y.exp() / mean_x.exp(), 1.0));
+        }
+        let exponent = covariance / variance;
+        Some(((mean_y - exponent * mean_x).exp(), exponent))
+    }
+}
+
+/// Impact of selling `notional_usd` into a constant-product pool holding
+/// `depth_usd` on each side: the output falls short of the spot-priced
+/// output by `notional / (depth + notional)`.
+fn constant_product_impact(depth_usd: f64, notional_usd: f64) -> f64 {
+    notional_usd / (depth_usd + notional_usd)
+}
+
+fn pair_key(a: &Token, b: &Token) -> (String, String) {
+    if a.mint <= b.mint {
+        (a.mint.clone(), b.mint.clone())
+    } else {
+        (b.mint.clone(), a.mint.clone())
+    }
+}
+
+/// Periodically quotes every strategy pair on Jupiter at the configured
+/// probe sizes to keep the fitted impact curves current.
+pub async fn run_probes(state: AppState, config: Arc<Config>, jupiter: Arc<JupiterClient>) {
+    let ImpactConfig::Fitted {
+        probe_sizes_usd,
+        probe_interval_secs,
+        ..
+    } = &config.impact
+    else {
+        return;
+    };
+    if probe_sizes_usd.is_empty() {
+        return;
+    }
+
+    let mut ticker = interval(Duration::from_secs(*probe_interval_secs));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        ticker.tick().await;
+        let pairs = {
+            let data = state.lock().await;
+            let mut pairs: Vec<(TradingPair, f64)> = Vec::new();
+            for strategy in &data.strategies {
+                let pair = &strategy.pair;
+                if pairs
+                    .iter()
+                    .any(|(known, _)| known.base == pair.base && known.quote == pair.quote)
+                {
+                    continue;
+                }
+                if let Some(quote_usd) = pair.quote.usd_price(&data.latest_prices) {
+                    pairs.push((pair.clone(), quote_usd));
+                }
+            }
+            pairs
+        };
+
+        for (pair, quote_usd) in pairs {
+            for size_usd in probe_sizes_usd {
+                let amount_base_units = pair.quote.to_base_units(size_usd / quote_usd);
+                if amount_base_units == 0 {
+                    continue;
+                }
+                match jupiter
+                    .quote_exact_in(&pair.quote.mint, &pair.base.mint, amount_base_units)
+                    .await
+                {
+                    Ok(quote) => {
+                        if let Some(impact) = quote.price_impact_pct {
+                            state.lock().await.impact.observe(
+                                &pair.quote,
+                                &pair.base,
+                                *size_usd,
+                                impact,
+                            );
+                        }
+                    }
+                    Err(err) => eprintln!("impact probe failed: {err:?}"),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokens::TokenRegistry;
+
+    fn model(toml: &str) -> (ImpactModel, Token, Token) {
+        let config: Config = toml::from_str(toml).unwrap();
+        let tokens = TokenRegistry::from_config(&config).unwrap();
+        let sol = tokens.by_symbol("SOL").unwrap().clone();
+        let usdc = tokens.by_symbol("USDC").unwrap().clone();
+        (ImpactModel::new(&config.impact), sol, usdc)
+    }
+
+    fn close(actual: f64, expected: f64) -> bool {
+        (actual - expected).abs() < 1e-9
+    }
+
+    #[test]
+    fn no_model_charges_nothing() {
+        let (model, sol, usdc) = model("");
+        assert_eq!(model.impact(&sol, &usdc, 1e6), None);
+    }
+
+    #[test]
+    fn constant_product_uses_the_pair_depth_in_either_order() {
+        let (model, sol, usdc) = model(
+            r#"impact = { model = "constant_product", liquidity_usd = 1000.0, pools = { "SOL/USDC" = 9000.0 } }"#,
+        );
+        assert!(close(model.impact(&sol, &usdc, 1000.0).unwrap(), 0.1));
+        assert!(close(model.impact(&usdc, &sol, 1000.0).unwrap(), 0.1));
+        assert!(close(model.impact(&sol, &usdc, 0.0).unwrap(), 0.0));
+    }
+
+    #[test]
+    fn impact_is_capped() {
+        let (model, sol, usdc) =
+            model(r#"impact = { model = "constant_product", liquidity_usd = 1.0 }"#);
+        assert_eq!(model.impact(&sol, &usdc, 1e9), Some(MAX_IMPACT));
+    }
+
+    #[test]
+    fn fitted_model_recovers_a_power_law() {
+        let (mut model, sol, usdc) = model(
+            r#"impact = { model = "fitted", max_samples = 10, fallback_liquidity_usd = 1000.0 }"#,
+        );
+        // Falls back to the constant-product depth until two samples land.
+        assert!(close(model.impact(&sol, &usdc, 1000.0).unwrap(), 0.5));
+        for notional in [100.0, 1_000.0, 10_000.0] {
+            let impact: f64 = 1e-4 * f64::powf(notional, 0.5);
+            model.observe(&usdc, &sol, notional, impact);
+        }
+        let impact = model.impact(&sol, &usdc, 40_000.0).unwrap();
+        assert!(close(impact, 1e-4 * 200.0), "{impact}");
+    }
+
+    #[test]
+    fn fitted_samples_keep_the_most_recent() {
+        let (mut model, sol, usdc) = model(r#"impact = { model = "fitted", max_samples = 2 }"#);
+        model.observe(&sol, &usdc, 100.0, 0.5);
+        model.observe(&sol, &usdc, 100.0, 0.01);
+        model.observe(&sol, &usdc, 200.0, 0.02);
+        // Only the linear samples remain.
+        assert!(close(model.impact(&sol, &usdc, 300.0).unwrap(), 0.03));
+        // Non-positive samples are ignored.
+        model.observe(&sol, &usdc, 0.0, 0.5);
+        model.observe(&sol, &usdc, 100.0, -0.5);
+        assert!(close(model.impact(&sol, &usdc, 300.0).unwrap(), 0.03));
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::VecDeque;
+
+/// Simple moving average over the last `window` samples.
+#[derive(Debug, Clone)]
+pub struct Sma {
+    window: usize,
+    values: VecDeque<f64>,
+    sum: f64,
+}
+
+impl Sma {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            values: VecDeque::with_capacity(window),
+            sum: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, value: f64) -> Option<f64> {
+        self.values.push_back(value);
+        self.sum += value;
+        if self.values.len() > self.window
+            && let Some(old) = self.values.pop_front()
+        {
+            self.sum -= old;
+        }
+        self.value()
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        (self.values.len() == self.window).then(|| self.sum / self.window as f64)
+    }
+}
+
+/// Exponential moving average seeded with the SMA of its first `window`
+/// samples.
+#[derive(Debug, Clone)]
+pub struct Ema {
+    alpha: f64,
+    seed: Sma,
+    value: Option<f64>,
+}
+
+impl Ema {
+    pub fn new(window: usize) -> Self {
+        Self {
+            alpha: 2.0 / (window as f64 + 1.0),
+            seed: Sma::new(window),
+            value: None,
+        }
+    }
+
+    pub fn update(&mut self, value: f64) -> Option<f64> {
+        self.value = match self.value {
+            Some(previous) => Some(previous + self.alpha * (value - previous)),
+            None => self.seed.update(value),
+        };
+        self.value
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        self.value
+    }
+}
+
+/// Relative strength index using Wilder's smoothing.
+#[derive(Debug, Clone)]
+pub struct Rsi {
+    period: usize,
+    previous: Option<f64>,
+    samples: usize,
+    avg_gain: f64,
+    avg_loss: f64,
+}
+
+impl Rsi {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period,
+            previous: None,
+            samples: 0,
+            avg_gain: 0.0,
+            avg_loss: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, value: f64) -> Option<f64> {
+        let previous = self.previous.replace(value)?;
+        let change = value - previous;
+        let gain = change.max(0.0);
+        let loss = (-change).max(0.0);
+        let period = self.period as f64;
+
+        self.samples += 1;
+        if self.samples <= self.period {
+            // Plain average over the first `period` changes seeds the smoothing.
+            self.avg_gain += gain / period;
+            self.avg_loss += loss / period;
+        } else {
+            self.avg_gain = (self.avg_gain * (period - 1.0) + gain) / period;
+            self.avg_loss = (self.avg_loss * (period - 1.0) + loss) / period;
+        }
+        self.value()
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        if self.samples < self.period {
+            return None;
+        }
+        if self.avg_loss == 0.0 {
+            return Some(if self.avg_gain == 0.0 { 50.0 } else { 100.0 });
+        }
+        let rs = self.avg_gain / self.avg_loss;
+        Some(100.0 - 100.0 / (1.0 + rs))
+    }
+}
+
+/// Groups ticks into fixed-length time bars and yields each bar's close once
+/// a tick from a later bar arrives.
+#[derive(Debug, Clone)]
+pub struct BarAggregator {
+    seconds: i64,
+    bucket: Option<i64>,
+    close: f64,
+}
+
+impl BarAggregator {
+    pub fn new(seconds: u64) -> Self {
+        Self {
+            seconds: seconds.max(1) as i64,
+            bucket: None,
+            close: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, timestamp: i64, value: f64) -> Option<f64> {
+        let bucket = timestamp.div_euclid(self.seconds);
+        let completed = match self.bucket {
+            Some(current) if bucket > current => Some(self.close),
+            _ => None,
+        };
+        if self.bucket.is_none_or(|current| bucket >= current) {
+            self.bucket = Some(bucket);
+            self.close = value;
+        }
+        completed
+    }
+}
+
+/// Mean and population standard deviation over the last `window` samples.
+#[derive(Debug, Clone)]
+pub struct RollingStats {
+    window: usize,
+    values: VecDeque<f64>,
+}
+
+impl RollingStats {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            values: VecDeque::with_capacity(window),
+        }
+    }
+
+    pub fn update(&mut self, value: f64) -> Option<(f64, f64)> {
+        self.values.push_back(value);
+        if self.values.len() > self.window {
+            self.values.pop_front();
+        }
+        self.value()
+    }
+
+    /// Returns `(mean, std_dev)` once the window is full.
+    pub fn value(&self) -> Option<(f64, f64)> {
+        if self.values.len() < self.window {
+            return None;
+        }
+        let count = self.window as f64;
+        let mean = self.values.iter().sum::<f64>() / count;
+        let variance = self
+            .values
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        Some((mean, variance.sqrt()))
+    }
+}
+
+/// Seconds in a year of round-the-clock trading.
+pub const SECONDS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0;
+
+/// Annualized volatility of log returns over the last `window` returns,
+/// scaled by the average spacing between samples.
+#[derive(Debug, Clone)]
+pub struct RealizedVol {
+    window: usize,
+    /// `(timestamp, price)` samples; one more than `window`.
+    samples: VecDeque<(i64, f64)>,
+}
+
+impl RealizedVol {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            samples: VecDeque::with_capacity(window + 1),
+        }
+    }
+
+    pub fn update(&mut self, timestamp: i64, price: f64) -> Option<f64> {
+        if price > 0.0 {
+            self.samples.push_back((timestamp, price));
+            if self.samples.len() > self.window + 1 {
+                self.samples.pop_front();
+            }
+        }
+        self.value()
+    }
+
+    pub fn value(&self) -> Option<f64> {
+        if self.samples.len() <= self.window {
+            return None;
+        }
+        let (first_time, _) = *self.samples.front()?;
+        let (last_time, _) = *self.samples.back()?;
+        let span = (last_time - first_time) as f64;
+        if span <= 0.0 {
+            return None;
+        }
+
+        let returns = self
+            .samples
+            .iter()
+            .zip(self.samples.iter().skip(1))
+            .map(|((_, previous), (_, current))| (current / previous).ln())
+            .collect::<Vec<_>>();
+        let count = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / count;
+        let variance = returns
+            .iter()
+            .map(|value| (value - mean).powi(2))
+            .sum::<f64>()
+            / (count - 1.0);
+        let periods_per_year = SECONDS_PER_YEAR / (span / count);
+        Some((variance * periods_per_year).sqrt())
+    }
+}
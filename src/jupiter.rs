@@ -1,4 +1,4 @@
-use crate::config::{DEFAULT_SLIPPAGE_BPS, JUPITER_BASE_URL, JUPITER_USER_PUBKEY};
+use crate::config::JupiterConfig;
 use anyhow::{Result, anyhow};
 use reqwest::Client;
 use serde_json::Value;
@@ -6,12 +6,16 @@ use serde_json::Value;
 #[derive(Clone)]
 pub struct JupiterClient {
     http: Client,
+    config: JupiterConfig,
 }
 
 impl JupiterClient {
-    pub fn new() -> Result<Self> {
+    pub fn new(config: &JupiterConfig) -> Result<Self> {
         let http = Client::builder().build()?;
-        Ok(Self { http })
+        Ok(Self {
+            http,
+            config: config.clone(),
+        })
     }
 
     pub async fn quote_exact_in(
@@ -20,7 +24,7 @@ impl JupiterClient {
         output_mint: &str,
         amount: u64,
     ) -> Result<JupiterQuote> {
-        let url = format!("{}/quote", JUPITER_BASE_URL);
+        let url = format!("{}/quote", self.config.base_url);
         let response = self
             .http
             .get(url)
@@ -29,7 +33,7 @@ impl JupiterClient {
                 ("outputMint", output_mint),
                 ("amount", &amount.to_string()),
                 ("swapMode", "ExactIn"),
-                ("slippageBps", &DEFAULT_SLIPPAGE_BPS.to_string()),
+                ("slippageBps", &self.config.slippage_bps.to_string()),
             ])
             .send()
             .await?
@@ -55,10 +59,10 @@ impl JupiterClient {
         quote: &JupiterQuote,
         wrap_and_unwrap_sol: bool,
     ) -> Result<JupiterSimulation> {
-        let url = format!("{}/swap", JUPITER_BASE_URL);
+        let url = format!("{}/swap", self.config.base_url);
         let body = serde_json::json!({
             "quoteResponse": quote.raw,
-            "userPublicKey": JUPITER_USER_PUBKEY,
+            "userPublicKey": self.config.user_pubkey,
             "wrapAndUnwrapSol": wrap_and_unwrap_sol,
             "simulate": true,
             "asLegacyTransaction": true,
@@ -85,18 +89,18 @@ impl JupiterClient {
             ));
         }
 
-        let lamports_fee = raw
+        let base_fee_lamports = raw
             .get("lamportsFee")
             .and_then(|value| value.as_u64())
             .unwrap_or(5000);
-        let prioritization_fee = raw
+        let priority_fee_lamports = raw
             .get("prioritizationFeeLamports")
             .and_then(|value| value.as_u64())
             .unwrap_or(0);
-        let gas_lamports = lamports_fee + prioritization_fee;
 
         Ok(JupiterSimulation {
-            gas_lamports: Some(gas_lamports),
+            base_fee_lamports,
+            priority_fee_lamports,
         })
     }
 }
@@ -111,7 +115,8 @@ pub struct JupiterQuote {
 
 #[derive(Debug, Clone)]
 pub struct JupiterSimulation {
-    pub gas_lamports: Option<u64>,
+    pub base_fee_lamports: u64,
+    pub priority_fee_lamports: u64,
 }
 
 fn parse_amount(value: &Value, key: &str) -> Result<u64> {
@@ -0,0 +1,98 @@
+use crate::config::LatencyConfig;
+use chrono::TimeDelta;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, LogNormal};
+
+/// Draws the signal-to-fill delay of each swap.
+#[derive(Debug)]
+pub struct LatencyModel {
+    config: LatencyConfig,
+    rng: StdRng,
+}
+
+impl LatencyModel {
+    pub fn new(config: &LatencyConfig, seed: Option<u64>) -> Self {
+        Self {
+            config: config.clone(),
+            rng: match seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+        }
+    }
+
+    /// Delay before the next swap fills, or `None` to fill immediately.
+    pub fn sample(&mut self) -> Option<TimeDelta> {
+        let millis = match self.config {
+            LatencyConfig::None => return None,
+            LatencyConfig::Fixed { millis } => millis as f64,
+            LatencyConfig::Uniform {
+                min_millis,
+                max_millis,
+            } => self.rng.gen_range(min_millis..=max_millis) as f64,
+            LatencyConfig::LogNormal {
+                median_millis,
+                sigma,
+            } => LogNormal::new(median_millis.ln(), sigma)
+                .map(|distribution| distribution.sample(&mut self.rng))
+                .unwrap_or(median_millis),
+        };
+        let millis = millis.round() as i64;
+        (millis > 0).then(|| TimeDelta::milliseconds(millis))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(config: LatencyConfig, seed: u64) -> Vec<Option<TimeDelta>> {
+        let mut model = LatencyModel::new(&config, Some(seed));
+        (0..50).map(|_| model.sample()).collect()
+    }
+
+    #[test]
+    fn no_latency_fills_immediately() {
+        assert!(samples(LatencyConfig::None, 1).iter().all(Option::is_none));
+        let zero = LatencyConfig::Fixed { millis: 0 };
+        assert!(samples(zero, 1).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn fixed_latency_is_exact() {
+        let delays = samples(LatencyConfig::Fixed { millis: 250 }, 1);
+        assert!(
+            delays
+                .iter()
+                .all(|delay| *delay == Some(TimeDelta::milliseconds(250)))
+        );
+    }
+
+    #[test]
+    fn uniform_latency_stays_in_bounds_and_repeats_by_seed() {
+        let config = LatencyConfig::Uniform {
+            min_millis: 100,
+            max_millis: 200,
+        };
+        let delays = samples(config.clone(), 9);
+        for delay in &delays {
+            let millis = delay.unwrap().num_milliseconds();
+            assert!((100..=200).contains(&millis), "{millis}");
+        }
+        assert_eq!(delays, samples(config, 9));
+    }
+
+    #[test]
+    fn log_normal_without_spread_is_the_median() {
+        let config = LatencyConfig::LogNormal {
+            median_millis: 400.0,
+            sigma: 0.0,
+        };
+        assert!(
+            samples(config, 3)
+                .iter()
+                .all(|delay| *delay == Some(TimeDelta::milliseconds(400)))
+        );
+    }
+}
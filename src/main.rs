@@ -1,21 +1,55 @@
+mod backtest;
+mod benchmark;
 mod config;
+mod fees;
+mod impact;
+mod indicators;
 mod jupiter;
+mod latency;
+mod metrics;
 mod model;
+mod monte_carlo;
+mod orders;
 mod price_stream;
+mod risk;
+mod scheduler;
 mod simulation;
+mod sizing;
+mod strategies;
+mod sweep;
+mod synthetic;
+mod tokens;
+mod walk_forward;
 mod web;
 
 use axum::{Router, routing::get};
-use config::{PriceFeed, jupiter_enabled};
+use clap::Parser;
+use config::{Cli, Command, Config};
 use jupiter::JupiterClient;
-use model::{AppData, AppState, StrategyData};
+use model::{AppData, AppState};
 use std::sync::Arc;
+use tokens::TokenRegistry;
 use tokio::sync::Mutex;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let jupiter = if jupiter_enabled() {
-        match JupiterClient::new() {
+    dotenv::dotenv().ok();
+    let cli = Cli::parse();
+    let config = Arc::new(Config::from_cli(&cli)?);
+    match &cli.command {
+        Some(Command::Backtest(args)) => return backtest::run_cli(&config, args),
+        Some(Command::Sweep(args)) => return sweep::run_cli(&config, args),
+        Some(Command::WalkForward(args)) => return walk_forward::run_cli(&config, args),
+        Some(Command::MonteCarlo(args)) => return monte_carlo::run_cli(&config, args),
+        None => {}
+    }
+
+    // Live quotes would price swaps off the real market, not the synthetic one.
+    let jupiter = if config.jupiter.enabled && config.synthetic.enabled {
+        eprintln!("Jupiter integration ignored in the synthetic market; using local pricing.");
+        None
+    } else if config.jupiter.enabled {
+        match JupiterClient::new(&config.jupiter) {
             Ok(client) => {
                 eprintln!("Jupiter integration enabled; using live quotes.");
                 Some(Arc::new(client))
@@ -31,25 +65,47 @@ async fn main() -> anyhow::Result<()> {
         eprintln!("Jupiter integration disabled; using local pricing for swaps.");
         None
     };
-    let state: AppState = Arc::new(Mutex::new(AppData {
-        latest_price: None,
-        strategies: vec![
-            StrategyData::alternating(),
-            StrategyData::trend_follow(),
-            StrategyData::range_trader(),
-        ],
-        history: Vec::new(),
-    }));
+    let tokens = Arc::new(TokenRegistry::from_config(&config)?);
+    let state: AppState = Arc::new(Mutex::new(AppData::new(&config, &tokens)?));
 
-    tokio::spawn(price_stream::run(
+    if config.synthetic.enabled {
+        eprintln!("Synthetic market enabled; generating prices instead of streaming Hermes.");
+        tokio::spawn(synthetic::run(
+            synthetic::SyntheticMarket::new(&config)?,
+            state.clone(),
+            config.clone(),
+            jupiter.clone(),
+        ));
+    } else {
+        tokio::spawn(price_stream::run(
+            state.clone(),
+            config.clone(),
+            jupiter.clone(),
+        ));
+    }
+    tokio::spawn(scheduler::run(
         state.clone(),
+        config.clone(),
         jupiter.clone(),
-        PriceFeed::SolUsd,
     ));
+    if let Some(client) = &jupiter {
+        tokio::spawn(impact::run_probes(
+            state.clone(),
+            config.clone(),
+            client.clone(),
+        ));
+    }
 
-    let app = Router::new().route("/", get(web::index)).with_state(state);
+    let app = Router::new()
+        .route("/", get(web::index))
+        .route("/api/strategies", get(web::strategies))
+        .with_state(web::WebState {
+            app: state,
+            config: config.clone(),
+            tokens,
+        });
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3001")
+    let listener = tokio::net::TcpListener::bind(&config.server.bind_addr)
         .await
         .expect("failed to bind server port");
 
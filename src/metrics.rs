@@ -0,0 +1,256 @@
+use crate::indicators::SECONDS_PER_YEAR;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Deviation of returns, relative to their mean, below which it is taken
+/// for floating-point rounding rather than volatility.
+const RETURN_NOISE: f64 = 1e-9;
+
+/// Running performance of one strategy, valued in its quote token. Equity is
+/// sampled on every tick of the strategy's pair, and sells close round trips
+/// against the average cost of the base held.
+#[derive(Debug, Clone, Default)]
+pub struct Performance {
+    initial_equity: Option<f64>,
+    last: Option<Sample>,
+    peak_equity: f64,
+    max_drawdown: f64,
+    elapsed_secs: f64,
+    /// Number of returns between samples, their running mean and sum of
+    /// squared deviations from it (Welford), and the sum of the squares of
+    /// the negative ones.
+    returns: u64,
+    return_mean: f64,
+    return_deviations: f64,
+    downside_squares: f64,
+    /// Time integrals of equity and of the share of equity held in base.
+    equity_secs: f64,
+    exposure_secs: f64,
+    /// Quote paid for the base held; starting base is marked at the first
+    /// price.
+    cost_basis: f64,
+    /// Quote traded in either direction.
+    volume: f64,
+    round_trips: u64,
+    winners: u64,
+    round_trip_pnl: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    time: DateTime<Utc>,
+    price: f64,
+    equity: f64,
+    exposure: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Metrics {
+    pub equity: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub total_return: f64,
+    /// Sample standard deviation of the returns between equity samples, one
+    /// per tick of the pair, scaled by the number of samples per year. The
+    /// ratios below are annualized the same way, so they extrapolate short
+    /// histories and are only comparable between runs over similar spans
+    /// and tick rates, not with ratios of daily returns.
+    pub annualized_volatility: Option<f64>,
+    /// Annualized mean return over `annualized_volatility`, with no
+    /// risk-free rate.
+    pub sharpe: Option<f64>,
+    /// Annualized mean return over the annualized root mean square of the
+    /// negative returns.
+    pub sortino: Option<f64>,
+    pub max_drawdown: f64,
+    pub round_trips: u64,
+    pub win_rate: Option<f64>,
+    /// Mean profit of a round trip, in quote.
+    pub average_trade: Option<f64>,
+    /// Quote traded over average equity.
+    pub turnover: Option<f64>,
+    /// Time-weighted share of equity held in base.
+    pub exposure: Option<f64>,
+}
+
+impl Performance {
+    /// Samples equity at a new pair price.
+    pub fn observe(&mut self, base: f64, quote: f64, price: f64, time: DateTime<Utc>) {
+        let base_value = base * price;
+        let equity = base_value + quote;
+        let exposure = if equity > 0.0 {
+            base_value / equity
+        } else {
+            0.0
+        };
+        match self.last {
+            None => {
+                self.initial_equity = Some(equity);
+                self.cost_basis = base_value;
+            }
+            Some(last) => {
+                let elapsed = (time - last.time).num_milliseconds() as f64 / 1000.0;
+                if elapsed > 0.0 {
+                    self.elapsed_secs += elapsed;
+                    self.equity_secs += last.equity * elapsed;
+                    self.exposure_secs += last.exposure * elapsed;
+                }
+                if last.equity > 0.0 {
+                    let change = equity / last.equity - 1.0;
+                    self.returns += 1;
+                    let delta = change - self.return_mean;
+                    self.return_mean += delta / self.returns as f64;
+                    self.return_deviations += delta * (change - self.return_mean);
+                    if change < 0.0 {
+                        self.downside_squares += change * change;
+                    }
+                }
+            }
+        }
+        self.peak_equity = self.peak_equity.max(equity);
+        if self.peak_equity > 0.0 {
+            self.max_drawdown = self.max_drawdown.max(1.0 - equity / self.peak_equity);
+        }
+        self.last = Some(Sample {
+            time,
+            price,
+            equity,
+            exposure,
+        });
+    }
+
+    pub fn record_buy(&mut self, quote_in: f64) {
+        self.cost_basis += quote_in;
+        self.volume += quote_in;
+    }
+
+    /// Closes a round trip selling `base_in` of the `base_before` held.
+    pub fn record_sell(&mut self, base_in: f64, quote_out: f64, base_before: f64) {
+        let closed_cost = if base_before > 0.0 {
+            self.cost_basis * (base_in / base_before).min(1.0)
+        } else {
+            0.0
+        };
+        self.cost_basis -= closed_cost;
+        let pnl = quote_out - closed_cost;
+        self.round_trips += 1;
+        if pnl > 0.0 {
+            self.winners += 1;
+        }
+        self.round_trip_pnl += pnl;
+        self.volume += quote_out;
+    }
+
+    /// Metrics for the given balances, marked at the last sampled price.
+    /// `None` until the first sample.
+    pub fn metrics(&self, base: f64, quote: f64) -> Option<Metrics> {
+        let last = self.last?;
+        let initial_equity = self.initial_equity?;
+        let base_value = base * last.price;
+        let equity = base_value + quote;
+        let unrealized_pnl = base_value - self.cost_basis;
+
+        // Per-return statistics, annualized by the number of returns per year.
+        // Deviations within rounding noise of the mean count as none.
+        let years = self.elapsed_secs / SECONDS_PER_YEAR;
+        let (annualized_volatility, sharpe, sortino) = if years > 0.0 && self.returns > 1 {
+            let count = self.returns as f64;
+            let per_year = count / years;
+            let mean = self.return_mean;
+            let noise = (RETURN_NOISE * mean.abs()).max(f64::EPSILON);
+            let deviation = (self.return_deviations / (count - 1.0)).max(0.0).sqrt();
+            let deviation = if deviation > noise { deviation } else { 0.0 };
+            let downside = (self.downside_squares / count).sqrt();
+            let downside = if downside > noise { downside } else { 0.0 };
+            let drift = mean * per_year;
+            let volatility = deviation * per_year.sqrt();
+            let downside = downside * per_year.sqrt();
+            (
+                Some(volatility),
+                (volatility > 0.0).then(|| drift / volatility),
+                (downside > 0.0).then(|| drift / downside),
+            )
+        } else {
+            (None, None, None)
+        };
+        let round_trips = self.round_trips as f64;
+        let average_equity = self.equity_secs / self.elapsed_secs;
+
+        Some(Metrics {
+            equity,
+            realized_pnl: equity - initial_equity - unrealized_pnl,
+            unrealized_pnl,
+            total_return: if initial_equity > 0.0 {
+                equity / initial_equity - 1.0
+            } else {
+                0.0
+            },
+            annualized_volatility,
+            sharpe,
+            sortino,
+            max_drawdown: self.max_drawdown,
+            round_trips: self.round_trips,
+            win_rate: (self.round_trips > 0).then(|| self.winners as f64 / round_trips),
+            average_trade: (self.round_trips > 0).then(|| self.round_trip_pnl / round_trips),
+            turnover: (average_equity > 0.0).then(|| self.volume / average_equity),
+            exposure: (self.elapsed_secs > 0.0).then(|| self.exposure_secs / self.elapsed_secs),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn daily_curve_has_known_ratios_and_drawdown() {
+        let start = DateTime::<Utc>::UNIX_EPOCH;
+        let mut performance = Performance::default();
+        for (day, equity) in [100.0, 110.0, 99.0, 108.9].into_iter().enumerate() {
+            performance.observe(0.0, equity, 1.0, start + TimeDelta::days(day as i64));
+        }
+        let metrics = performance.metrics(0.0, 108.9).unwrap();
+
+        // Returns of +10%, -10%, +10%: mean 1/30, sample variance 1/75 and
+        // downside variance 1/300, sampled once a day.
+        let per_year = SECONDS_PER_YEAR / 86_400.0;
+        assert_close(metrics.total_return, 0.089);
+        assert_close(metrics.max_drawdown, 0.1);
+        assert_close(
+            metrics.annualized_volatility.unwrap(),
+            (per_year / 75.0).sqrt(),
+        );
+        assert_close(
+            metrics.sharpe.unwrap(),
+            (1.0 / 30.0) * (75.0 * per_year).sqrt(),
+        );
+        assert_close(
+            metrics.sortino.unwrap(),
+            (1.0 / 30.0) * (300.0 * per_year).sqrt(),
+        );
+    }
+
+    #[test]
+    fn constant_returns_have_no_volatility() {
+        let start = DateTime::<Utc>::UNIX_EPOCH;
+        let mut performance = Performance::default();
+        // 1% a day; rounding leaves the returns a few ulps apart.
+        let equity = |day: i32| 100.0 * 1.01_f64.powi(day);
+        for day in 0..60 {
+            performance.observe(0.0, equity(day), 1.0, start + TimeDelta::days(day.into()));
+        }
+        let metrics = performance.metrics(0.0, equity(59)).unwrap();
+        assert_eq!(metrics.annualized_volatility, Some(0.0));
+        assert_eq!(metrics.max_drawdown, 0.0);
+        assert!(metrics.sharpe.is_none());
+        assert!(metrics.sortino.is_none());
+    }
+}
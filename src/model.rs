@@ -1,4 +1,19 @@
+use crate::benchmark::{BenchmarkResult, Benchmarks};
+use crate::config::Config;
+use crate::fees::{FeeBreakdown, FeeModel};
+use crate::impact::ImpactModel;
+use crate::latency::LatencyModel;
+use crate::metrics::{Metrics, Performance};
+use crate::orders::OrderBook;
+use crate::risk::RiskManager;
+use crate::simulation::PendingAction;
+use crate::sizing::Sizer;
+use crate::strategies::{Strategy, StrategyRegistry};
+use crate::tokens::{Token, TokenRegistry, TradingPair};
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -8,120 +23,151 @@ pub struct PriceInfo {
     pub publish_time: Option<i64>,
 }
 
-#[derive(Debug, Clone)]
+/// Token balances in base units, keyed by mint.
+#[derive(Debug, Clone, Default)]
 pub struct WalletState {
-    pub sol: f64,
-    pub usdc: f64,
+    balances: BTreeMap<String, u64>,
 }
 
-#[derive(Debug, Clone, Copy)]
-pub enum Token {
-    Sol,
-    Usdc,
-}
+impl WalletState {
+    pub fn units(&self, token: &Token) -> u64 {
+        self.balances.get(&token.mint).copied().unwrap_or(0)
+    }
 
-impl Token {
-    pub fn symbol(self) -> &'static str {
-        match self {
-            Token::Sol => "SOL",
-            Token::Usdc => "USDC",
-        }
+    /// Balance in whole tokens.
+    pub fn balance(&self, token: &Token) -> f64 {
+        token.to_whole(self.units(token))
     }
-}
 
-#[derive(Debug, Clone, Copy)]
-pub enum SwapDirection {
-    ToUsdc,
-    ToSol,
-}
+    pub fn credit(&mut self, token: &Token, units: u64) -> Result<()> {
+        let balance = self.balances.entry(token.mint.clone()).or_insert(0);
+        *balance = balance
+            .checked_add(units)
+            .ok_or_else(|| anyhow!("{} balance overflows", token.symbol))?;
+        Ok(())
+    }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum StrategyId {
-    Alternating,
-    TrendFollow,
-    RangeTrader,
-}
+    /// Fails without changing the wallet if the balance is too small.
+    pub fn debit(&mut self, token: &Token, units: u64) -> Result<()> {
+        let balance = self.units(token);
+        let Some(remaining) = balance.checked_sub(units) else {
+            bail!(
+                "insufficient {}: holds {} but needs {}",
+                token.symbol,
+                token.to_whole(balance),
+                token.to_whole(units)
+            );
+        };
+        self.balances.insert(token.mint.clone(), remaining);
+        Ok(())
+    }
 
-impl StrategyId {
-    pub fn label(self) -> &'static str {
-        match self {
-            StrategyId::Alternating => "Alternating",
-            StrategyId::TrendFollow => "Trend Follow",
-            StrategyId::RangeTrader => "Range Trader",
-        }
+    /// Non-zero balances as `(mint, base units)` pairs.
+    pub fn holdings(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.balances
+            .iter()
+            .filter(|(_, units)| **units > 0)
+            .map(|(mint, units)| (mint.as_str(), *units))
     }
-}
 
-#[derive(Debug, Clone)]
-pub enum StrategyState {
-    Alternating { next_swap: SwapDirection },
-    TrendFollow { last_price: Option<f64> },
-    RangeTrader { last_price: Option<f64> },
+    /// Total USD value of every holding, or `None` while any held token has
+    /// no price yet.
+    pub fn value_usd(
+        &self,
+        tokens: &TokenRegistry,
+        latest_prices: &HashMap<String, PriceInfo>,
+    ) -> Option<f64> {
+        self.holdings().try_fold(0.0, |total, (mint, units)| {
+            let token = tokens.by_mint(mint)?;
+            let price = token.usd_price(latest_prices)?;
+            Some(total + token.to_whole(units) * price)
+        })
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct StrategyData {
-    pub id: StrategyId,
+    pub strategy: Box<dyn Strategy>,
+    /// Pair traded by the strategy; ticks of the base token's feed drive it.
+    pub pair: TradingPair,
     pub wallet: WalletState,
-    pub state: StrategyState,
+    pub sizer: Option<Sizer>,
+    pub risk: Option<RiskManager>,
+    pub orders: OrderBook,
+    pub performance: Performance,
+    pub benchmarks: Benchmarks,
 }
 
 impl StrategyData {
-    pub fn alternating() -> Self {
-        Self {
-            id: StrategyId::Alternating,
-            wallet: WalletState {
-                sol: 1.0,
-                usdc: 0.0,
-            },
-            state: StrategyState::Alternating {
-                next_swap: SwapDirection::ToUsdc,
-            },
-        }
-    }
-
-    pub fn trend_follow() -> Self {
-        Self {
-            id: StrategyId::TrendFollow,
-            wallet: WalletState {
-                sol: 1.0,
-                usdc: 0.0,
-            },
-            state: StrategyState::TrendFollow { last_price: None },
-        }
+    pub fn metrics(&self) -> Option<Metrics> {
+        self.performance.metrics(
+            self.wallet.balance(&self.pair.base),
+            self.wallet.balance(&self.pair.quote),
+        )
     }
 
-    pub fn range_trader() -> Self {
-        Self {
-            id: StrategyId::RangeTrader,
-            wallet: WalletState {
-                sol: 1.0,
-                usdc: 0.0,
-            },
-            state: StrategyState::RangeTrader { last_price: None },
-        }
+    pub fn benchmark_results(&self) -> Vec<BenchmarkResult> {
+        let total_return = self.metrics().map(|metrics| metrics.total_return);
+        self.benchmarks.results(&self.pair, total_return)
     }
 }
 
 #[derive(Debug)]
 pub struct SwapRecord {
-    pub timestamp: String,
+    pub timestamp: DateTime<Utc>,
     pub direction: String,
     pub price: f64,
-    pub amount_in: f64,
-    pub amount_out: f64,
-    pub strategy: StrategyId,
+    /// Index of the strategy in `AppData::strategies`.
+    pub strategy_index: usize,
+    /// Amount swapped in, in base units of `input_token`.
+    pub amount_in: u64,
+    /// Amount received net of DEX and platform fees, in base units of
+    /// `output_token`.
+    pub amount_out: u64,
+    /// Label of the strategy that made the swap.
+    pub strategy: String,
     pub input_token: Token,
     pub output_token: Token,
-    pub gas_lamports: Option<u64>,
+    pub fees: FeeBreakdown,
     pub price_impact_pct: Option<f64>,
+    /// Indicator values the strategy reported when it decided to swap.
+    pub indicators: Vec<(String, f64)>,
+    /// Profit the strategy realized by closing a round trip with this swap,
+    /// in units of the output token.
+    pub realized_profit: Option<f64>,
+    /// Pair price when the strategy decided; `price` is the fill price.
+    pub decision_price: f64,
+    /// Delay between decision and fill, when latency is simulated.
+    pub fill_delay_ms: Option<i64>,
 }
 
 #[derive(Debug)]
 pub struct AppData {
-    pub latest_price: Option<PriceInfo>,
+    /// Most recent price per Hermes feed, keyed by normalized feed id.
+    pub latest_prices: HashMap<String, PriceInfo>,
     pub strategies: Vec<StrategyData>,
     pub history: Vec<SwapRecord>,
+    pub fees: FeeModel,
+    pub impact: ImpactModel,
+    pub latency: LatencyModel,
+    /// Swaps decided but not yet filled because of simulated latency.
+    pub in_flight: Vec<PendingAction>,
+}
+
+impl AppData {
+    /// Fresh simulation state with every configured strategy at its initial
+    /// balances.
+    pub fn new(config: &Config, tokens: &TokenRegistry) -> Result<Self> {
+        Ok(Self {
+            latest_prices: HashMap::new(),
+            strategies: StrategyRegistry::with_builtins().build_all(config, tokens)?,
+            history: Vec::new(),
+            fees: FeeModel::new(&config.fees, tokens),
+            impact: ImpactModel::new(&config.impact),
+            latency: LatencyModel::new(&config.latency, config.simulation.seed),
+            in_flight: Vec::new(),
+        })
+    }
 }
 
 pub type AppState = Arc<Mutex<AppData>>;
@@ -134,6 +180,7 @@ pub struct HermesResponse {
 
 #[derive(Debug, Deserialize)]
 pub struct ParsedPrice {
+    pub id: String,
     pub price: ParsedPriceData,
 }
 
@@ -144,3 +191,81 @@ pub struct ParsedPriceData {
     #[serde(default)]
     pub publish_time: Option<i64>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens() -> TokenRegistry {
+        TokenRegistry::from_config(&Config::default()).unwrap()
+    }
+
+    #[test]
+    fn credit_and_debit_move_base_units() {
+        let tokens = tokens();
+        let sol = tokens.by_symbol("SOL").unwrap();
+        let mut wallet = WalletState::default();
+        wallet.credit(sol, 1_500_000_000).unwrap();
+        wallet.debit(sol, 500_000_000).unwrap();
+        assert_eq!(wallet.units(sol), 1_000_000_000);
+        assert_eq!(wallet.balance(sol), 1.0);
+    }
+
+    #[test]
+    fn overdraft_fails_without_touching_the_balance() {
+        let tokens = tokens();
+        let usdc = tokens.by_symbol("USDC").unwrap();
+        let mut wallet = WalletState::default();
+        wallet.credit(usdc, 1_000_000).unwrap();
+        let error = wallet.debit(usdc, 1_000_001).unwrap_err();
+        assert!(error.to_string().contains("insufficient USDC"));
+        assert_eq!(wallet.units(usdc), 1_000_000);
+    }
+
+    #[test]
+    fn credit_overflow_fails_without_touching_the_balance() {
+        let tokens = tokens();
+        let usdc = tokens.by_symbol("USDC").unwrap();
+        let mut wallet = WalletState::default();
+        wallet.credit(usdc, u64::MAX).unwrap();
+        assert!(wallet.credit(usdc, 1).is_err());
+        assert_eq!(wallet.units(usdc), u64::MAX);
+    }
+
+    #[test]
+    fn emptied_balances_drop_out_of_holdings() {
+        let tokens = tokens();
+        let (sol, usdc) = (
+            tokens.by_symbol("SOL").unwrap(),
+            tokens.by_symbol("USDC").unwrap(),
+        );
+        let mut wallet = WalletState::default();
+        wallet.credit(sol, 10).unwrap();
+        wallet.credit(usdc, 20).unwrap();
+        wallet.debit(sol, 10).unwrap();
+        assert_eq!(
+            wallet.holdings().collect::<Vec<_>>(),
+            [(usdc.mint.as_str(), 20)]
+        );
+    }
+
+    #[test]
+    fn value_waits_for_every_held_token_to_be_priced() {
+        let tokens = tokens();
+        let sol = tokens.by_symbol("SOL").unwrap();
+        let usdc = tokens.by_symbol("USDC").unwrap();
+        let mut wallet = WalletState::default();
+        wallet.credit(sol, 2_000_000_000).unwrap();
+        wallet.credit(usdc, 50_000_000).unwrap();
+        let mut prices = HashMap::new();
+        assert_eq!(wallet.value_usd(&tokens, &prices), None);
+        prices.insert(
+            sol.feed_id.clone().unwrap(),
+            PriceInfo {
+                value: 150.0,
+                publish_time: None,
+            },
+        );
+        assert_eq!(wallet.value_usd(&tokens, &prices), Some(350.0));
+    }
+}
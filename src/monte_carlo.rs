@@ -0,0 +1,482 @@
+use crate::backtest::{self, EquityPoint, PricePoint, StrategyReport};
+use crate::config::{Config, MonteCarloArgs, Resample};
+use crate::model::PriceInfo;
+use anyhow::{Result, bail};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub resample: Resample,
+    pub runs: u32,
+    pub block: u32,
+    /// Seed the runs were drawn with; pass it as `--seed` to repeat them.
+    pub seed: u64,
+    pub strategies: Vec<Robustness>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Robustness {
+    pub label: String,
+    pub pair: String,
+    /// Result on the history as recorded.
+    pub actual: Option<Outcome>,
+    pub final_equity_usd: Option<Distribution>,
+    pub total_return: Option<Distribution>,
+    pub max_drawdown: Option<Distribution>,
+    /// Share of runs that lost money.
+    pub probability_of_loss: Option<f64>,
+    /// Share of runs whose return fell below the actual one.
+    pub actual_percentile: Option<f64>,
+    /// Why the strategy was left out of the resampled runs.
+    pub skipped: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Outcome {
+    pub final_equity_usd: f64,
+    pub total_return: f64,
+    pub max_drawdown: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Distribution {
+    pub mean: f64,
+    pub min: f64,
+    pub p5: f64,
+    pub p25: f64,
+    pub p50: f64,
+    pub p75: f64,
+    pub p95: f64,
+    pub max: f64,
+}
+
+pub fn run_cli(config: &Config, args: &MonteCarloArgs) -> Result<()> {
+    let prices = backtest::load_prices(config, &args.history)?;
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let report = run(config, &prices, args.resample, args.runs, args.block, seed)?;
+
+    let optional = |value: Option<f64>| {
+        value
+            .map(|value| format!("{:.2}", value * 100.0))
+            .unwrap_or_else(|| "--".to_string())
+    };
+    println!(
+        "{:<20}  {:>9}  {:>9}  {:>9}  {:>9}  {:>9}  {:>9}  {:>7}  {:>7}",
+        "strategy", "actual", "p5", "p50", "p95", "dd p50", "dd p95", "p(loss)", "rank"
+    );
+    for strategy in &report.strategies {
+        if let Some(reason) = &strategy.skipped {
+            println!("{:<20}  not resampled: {reason}", strategy.label);
+            continue;
+        }
+        let returns = strategy.total_return.as_ref();
+        let drawdowns = strategy.max_drawdown.as_ref();
+        println!(
+            "{:<20}  {:>8}%  {:>8}%  {:>8}%  {:>8}%  {:>8}%  {:>8}%  {:>6}%  {:>6}%",
+            strategy.label,
+            optional(strategy.actual.map(|actual| actual.total_return)),
+            optional(returns.map(|returns| returns.p5)),
+            optional(returns.map(|returns| returns.p50)),
+            optional(returns.map(|returns| returns.p95)),
+            optional(drawdowns.map(|drawdowns| drawdowns.p50)),
+            optional(drawdowns.map(|drawdowns| drawdowns.p95)),
+            optional(strategy.probability_of_loss),
+            optional(strategy.actual_percentile)
+        );
+    }
+    eprintln!(
+        "{} runs resampling {} in blocks of {}, seed {}",
+        report.runs,
+        match report.resample {
+            Resample::Prices => "prices",
+            Resample::Trades => "trades",
+        },
+        report.block,
+        report.seed
+    );
+
+    match &args.output {
+        Some(path) => backtest::write_json(Some(path), &report),
+        None => Ok(()),
+    }
+}
+
+/// Backtests `prices` once as recorded, then `runs` times resampled, and
+/// summarizes each strategy's outcomes. Run `n` draws from a generator
+/// seeded with `seed + n`, so results do not depend on thread scheduling.
+pub fn run(
+    config: &Config,
+    prices: &[PricePoint],
+    resample: Resample,
+    runs: u32,
+    block: u32,
+    seed: u64,
+) -> Result<Report> {
+    let actual = backtest::run(config, prices)?.strategies;
+    let block_len = block as usize;
+    let mut skipped = vec![None; actual.len()];
+    match resample {
+        Resample::Prices => {
+            let returns = prices.len() - recorded_prefix(prices);
+            if block_len >= returns {
+                bail!(
+                    "--block {block} must be shorter than the {returns} price returns after every feed's first tick"
+                );
+            }
+        }
+        Resample::Trades => {
+            for (strategy, skipped) in actual.iter().zip(&mut skipped) {
+                let intervals = trade_factors(strategy).len();
+                if block_len > 1 && block_len >= intervals {
+                    *skipped = Some(format!(
+                        "{intervals} stretches between trades, too few for blocks of {block}"
+                    ));
+                }
+            }
+        }
+    }
+    let rng = |run: u32| StdRng::seed_from_u64(seed.wrapping_add(run.into()));
+
+    let samples = match resample {
+        Resample::Prices => (0..runs)
+            .into_par_iter()
+            .map(|run| {
+                let path = resample_prices(prices, block_len, &mut rng(run));
+                Ok(backtest::run(config, &path)?
+                    .strategies
+                    .iter()
+                    .map(|strategy| outcome(&equity(&strategy.equity_curve)))
+                    .collect::<Vec<_>>())
+            })
+            .collect::<Result<Vec<_>>>()?,
+        Resample::Trades => {
+            let factors = actual.iter().map(trade_factors).collect::<Vec<_>>();
+            (0..runs)
+                .into_par_iter()
+                .map(|run| {
+                    let mut rng = rng(run);
+                    actual
+                        .iter()
+                        .zip(&factors)
+                        .zip(&skipped)
+                        .map(|((strategy, factors), skipped)| {
+                            if skipped.is_some() {
+                                return None;
+                            }
+                            let mut path = vec![strategy.initial_equity_usd?];
+                            for index in block_indices(factors.len(), block_len, &mut rng) {
+                                path.push(path[path.len() - 1] * factors[index]);
+                            }
+                            outcome(&path)
+                        })
+                        .collect()
+                })
+                .collect()
+        }
+    };
+
+    let strategies = actual
+        .iter()
+        .zip(skipped)
+        .enumerate()
+        .map(|(index, (strategy, skipped))| {
+            let outcomes = samples
+                .iter()
+                .filter_map(|run| run[index])
+                .collect::<Vec<_>>();
+            let actual = outcome(&equity(&strategy.equity_curve));
+            let share = |matches: &dyn Fn(&Outcome) -> bool| {
+                (!outcomes.is_empty()).then(|| {
+                    outcomes.iter().filter(|sample| matches(sample)).count() as f64
+                        / outcomes.len() as f64
+                })
+            };
+            Robustness {
+                label: strategy.label.clone(),
+                pair: strategy.pair.clone(),
+                final_equity_usd: distribution(
+                    outcomes.iter().map(|sample| sample.final_equity_usd),
+                ),
+                total_return: distribution(outcomes.iter().map(|sample| sample.total_return)),
+                max_drawdown: distribution(outcomes.iter().map(|sample| sample.max_drawdown)),
+                probability_of_loss: share(&|sample| sample.total_return < 0.0),
+                actual_percentile: actual
+                    .and_then(|actual| share(&|sample| sample.total_return < actual.total_return)),
+                actual,
+                skipped,
+            }
+        })
+        .collect();
+
+    Ok(Report {
+        resample,
+        runs,
+        block,
+        seed,
+        strategies,
+    })
+}
+
+/// Number of leading ticks up to and including the first tick of the last
+/// feed to appear. Resampled paths keep them as recorded, so every feed is
+/// priced however the rest is drawn.
+fn recorded_prefix(prices: &[PricePoint]) -> usize {
+    let mut seen = HashSet::new();
+    prices
+        .iter()
+        .enumerate()
+        .filter(|(_, point)| seen.insert(point.feed.as_str()))
+        .last()
+        .map_or(0, |(index, _)| index + 1)
+}
+
+/// A history on the original clock that opens with the recorded ticks up to
+/// every feed's first one, then repeats the tick-to-tick returns of randomly
+/// drawn blocks of the rest. Each feed compounds from its last recorded
+/// price in that opening.
+fn resample_prices(prices: &[PricePoint], block: usize, rng: &mut StdRng) -> Vec<PricePoint> {
+    let (recorded, rest) = prices.split_at(recorded_prefix(prices));
+    let mut last = HashMap::<&str, f64>::new();
+    let ratios = prices
+        .iter()
+        .map(|point| {
+            last.insert(point.feed.as_str(), point.price.value)
+                .map_or(1.0, |previous| point.price.value / previous)
+        })
+        .collect::<Vec<_>>();
+
+    last.clear();
+    for point in recorded {
+        last.insert(point.feed.as_str(), point.price.value);
+    }
+    let resampled = block_indices(rest.len(), block, rng)
+        .zip(rest)
+        .map(|(index, slot)| {
+            let source = &rest[index];
+            let level = last.entry(source.feed.as_str()).or_default();
+            *level *= ratios[recorded.len() + index];
+            let value = *level;
+            PricePoint {
+                time: slot.time,
+                feed: source.feed.clone(),
+                feed_id: source.feed_id.clone(),
+                price: PriceInfo {
+                    value,
+                    publish_time: Some(slot.time.timestamp()),
+                },
+            }
+        });
+    recorded.iter().cloned().chain(resampled).collect()
+}
+
+/// `len` indices into a sequence of `len` items, taken as runs of `block`
+/// consecutive ones from random starts, wrapping around the end. `block`
+/// must be shorter than `len` for runs to differ.
+fn block_indices(len: usize, block: usize, rng: &mut StdRng) -> impl Iterator<Item = usize> {
+    let starts = (0..len.div_ceil(block))
+        .map(|_| rng.gen_range(0..len))
+        .collect::<Vec<_>>();
+    starts
+        .into_iter()
+        .flat_map(move |start| (start..start + block).map(move |index| index % len))
+        .take(len)
+}
+
+/// Equity growth factors between a strategy's trades, including the stretch
+/// before the first and after the last.
+fn trade_factors(strategy: &StrategyReport) -> Vec<f64> {
+    let curve = &strategy.equity_curve;
+    if curve.is_empty() {
+        return Vec::new();
+    }
+    let mut boundaries = vec![0];
+    for trade in &strategy.trades {
+        let index = curve
+            .partition_point(|point| point.time <= trade.time)
+            .max(1)
+            - 1;
+        if boundaries.last() != Some(&index) {
+            boundaries.push(index);
+        }
+    }
+    if boundaries.last() != Some(&(curve.len() - 1)) {
+        boundaries.push(curve.len() - 1);
+    }
+    boundaries
+        .windows(2)
+        .filter(|pair| curve[pair[0]].equity_usd > 0.0)
+        .map(|pair| curve[pair[1]].equity_usd / curve[pair[0]].equity_usd)
+        .collect()
+}
+
+fn equity(curve: &[EquityPoint]) -> Vec<f64> {
+    curve.iter().map(|point| point.equity_usd).collect()
+}
+
+fn outcome(path: &[f64]) -> Option<Outcome> {
+    let (&initial, &last) = (path.first()?, path.last()?);
+    let mut peak = 0.0_f64;
+    let mut max_drawdown = 0.0_f64;
+    for &equity in path {
+        peak = peak.max(equity);
+        if peak > 0.0 {
+            max_drawdown = max_drawdown.max(1.0 - equity / peak);
+        }
+    }
+    Some(Outcome {
+        final_equity_usd: last,
+        total_return: if initial > 0.0 {
+            last / initial - 1.0
+        } else {
+            0.0
+        },
+        max_drawdown,
+    })
+}
+
+fn distribution(values: impl Iterator<Item = f64>) -> Option<Distribution> {
+    let mut values = values.collect::<Vec<_>>();
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let percentile = |quantile: f64| {
+        let position = quantile * (values.len() - 1) as f64;
+        let (low, high) = (position.floor() as usize, position.ceil() as usize);
+        values[low] + (values[high] - values[low]) * (position - low as f64)
+    };
+    Some(Distribution {
+        mean: values.iter().sum::<f64>() / values.len() as f64,
+        min: values[0],
+        p5: percentile(0.05),
+        p25: percentile(0.25),
+        p50: percentile(0.5),
+        p75: percentile(0.75),
+        p95: percentile(0.95),
+        max: values[values.len() - 1],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, TimeDelta, Utc};
+
+    fn point(seconds: i64, feed: &str, value: f64) -> PricePoint {
+        let time = DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(seconds);
+        PricePoint {
+            time,
+            feed: feed.to_string(),
+            feed_id: feed.to_string(),
+            price: PriceInfo {
+                value,
+                publish_time: Some(time.timestamp()),
+            },
+        }
+    }
+
+    #[test]
+    fn block_indices_cover_the_length_in_wrapping_runs() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for block in 1..10 {
+            let indices = block_indices(10, block, &mut rng).collect::<Vec<_>>();
+            assert_eq!(indices.len(), 10);
+            for run in indices.chunks(block) {
+                for pair in run.windows(2) {
+                    assert_eq!(pair[1], (pair[0] + 1) % 10);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn block_indices_of_an_empty_sequence_are_empty() {
+        let mut rng = StdRng::seed_from_u64(3);
+        assert_eq!(block_indices(0, 4, &mut rng).count(), 0);
+    }
+
+    #[test]
+    fn resampled_prices_start_at_the_recorded_level() {
+        // Every tick moves the price by the same factor, so any resampling
+        // reproduces the recorded path.
+        let prices = (0..6)
+            .map(|tick| point(tick, "SOL/USD", 100.0 * 1.1_f64.powi(tick as i32)))
+            .collect::<Vec<_>>();
+        for seed in 0..20 {
+            let path = resample_prices(&prices, 2, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(path.len(), prices.len());
+            for (resampled, recorded) in path.iter().zip(&prices) {
+                assert_eq!(resampled.time, recorded.time);
+                assert!((resampled.price.value - recorded.price.value).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn resampled_paths_keep_every_feed_priced() {
+        let prices = vec![
+            point(0, "SOL/USD", 100.0),
+            point(1, "SOL/USD", 110.0),
+            point(2, "JUP/USD", 0.5),
+            point(3, "SOL/USD", 121.0),
+            point(4, "JUP/USD", 0.25),
+            point(5, "SOL/USD", 133.1),
+        ];
+        assert_eq!(recorded_prefix(&prices), 3);
+        for seed in 0..20 {
+            let path = resample_prices(&prices, 1, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(path.len(), prices.len());
+            for (resampled, recorded) in path[..3].iter().zip(&prices) {
+                assert_eq!(resampled.price.value, recorded.price.value);
+            }
+            // Drawn SOL ticks raise SOL by a tenth from 110; drawn JUP ticks
+            // halve JUP from 0.5.
+            for resampled in &path[3..] {
+                let value = resampled.price.value;
+                let levels = match resampled.feed.as_str() {
+                    "SOL/USD" => vec![121.0, 133.1, 146.41],
+                    _ => vec![0.25, 0.125, 0.0625],
+                };
+                assert!(levels.iter().any(|level| (value - level).abs() < 1e-9));
+            }
+        }
+    }
+
+    #[test]
+    fn strategies_with_too_few_trades_are_skipped_in_their_own_row() {
+        let config: Config = toml::from_str(
+            r#"
+            [[strategies]]
+            kind = "alternating"
+            base = "SOL"
+            quote = "USDC"
+            initial_balances = { SOL = 1.0 }
+
+            [[strategies]]
+            kind = "trend_follow"
+            base = "SOL"
+            quote = "USDC"
+            initial_balances = { SOL = 1.0 }
+            params = { threshold = 0.5, sell_base_amount = 0.1, buy_quote_amount = 10.0 }
+            "#,
+        )
+        .unwrap();
+        let feed_id = config.hermes.feed_id("test", "SOL/USD").unwrap();
+        let prices = (0..20)
+            .map(|tick| PricePoint {
+                feed_id: feed_id.clone(),
+                ..point(tick, "SOL/USD", 100.0 + (tick % 3) as f64)
+            })
+            .collect::<Vec<_>>();
+        let report = run(&config, &prices, Resample::Trades, 10, 3, 1).unwrap();
+        let [alternating, trend] = &report.strategies[..] else {
+            panic!("expected two strategies");
+        };
+        assert!(alternating.skipped.is_none() && alternating.total_return.is_some());
+        assert!(trend.skipped.is_some() && trend.total_return.is_none());
+    }
+}
@@ -0,0 +1,345 @@
+use crate::config::ExitConfig;
+use crate::strategies::SwapAction;
+use crate::tokens::TradingPair;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Order resting in a strategy's book until the pair price triggers it.
+/// Strategies place orders through `Strategy::take_orders`; the config can
+/// seed a book through `orders` on a strategy entry.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Order {
+    pub side: OrderSide,
+    pub kind: OrderKind,
+    /// Base to sell for `SellBase`, quote to spend for `BuyBase`.
+    pub amount: f64,
+    /// Orders sharing a group are one-cancels-other: when one triggers the
+    /// rest of the group is cancelled.
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+impl Order {
+    pub fn validate(&self, section: &str) -> Result<()> {
+        if self.amount <= 0.0 {
+            bail!("{section}.amount must be positive, got {}", self.amount);
+        }
+        match self.kind {
+            OrderKind::Limit { price }
+            | OrderKind::StopLoss { price }
+            | OrderKind::TakeProfit { price } => {
+                if price <= 0.0 {
+                    bail!("{section}.price must be positive, got {price}");
+                }
+            }
+            OrderKind::TrailingStop { trail, .. } => {
+                if !(trail > 0.0 && trail < 1.0) {
+                    bail!("{section}.trail must be between 0 and 1, got {trail}");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderSide {
+    SellBase,
+    BuyBase,
+}
+
+/// Trigger condition, in pair price (quote per base).
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum OrderKind {
+    /// Sells at or above `price`, or buys at or below it.
+    Limit { price: f64 },
+    /// Sells at or below `price`, or buys at or above it.
+    StopLoss { price: f64 },
+    /// Sells at or above `price`, or buys at or below it, closing a position
+    /// in profit.
+    TakeProfit { price: f64 },
+    /// Stop that follows the best price seen since placement at a distance
+    /// of `trail`, as a fraction.
+    TrailingStop {
+        trail: f64,
+        #[serde(default)]
+        best: Option<f64>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RestingOrder {
+    pub id: u64,
+    #[serde(flatten)]
+    pub order: Order,
+}
+
+impl RestingOrder {
+    /// Folds `price` into trailing stops and reports whether the order fires.
+    fn update(&mut self, price: f64) -> bool {
+        let side = self.order.side;
+        match &mut self.order.kind {
+            OrderKind::Limit { price: limit } | OrderKind::TakeProfit { price: limit } => {
+                match side {
+                    OrderSide::SellBase => price >= *limit,
+                    OrderSide::BuyBase => price <= *limit,
+                }
+            }
+            OrderKind::StopLoss { price: stop } => match side {
+                OrderSide::SellBase => price <= *stop,
+                OrderSide::BuyBase => price >= *stop,
+            },
+            OrderKind::TrailingStop { trail, best } => {
+                let best = best.get_or_insert(price);
+                match side {
+                    OrderSide::SellBase => {
+                        *best = best.max(price);
+                        price <= *best * (1.0 - *trail)
+                    }
+                    OrderSide::BuyBase => {
+                        *best = best.min(price);
+                        price >= *best * (1.0 + *trail)
+                    }
+                }
+            }
+        }
+    }
+
+    /// Market swap that fills the order, capped at the wallet's balance.
+    pub fn to_action(&self, pair: &TradingPair, available: f64) -> SwapAction {
+        let amount = self.order.amount.min(available);
+        match self.order.side {
+            OrderSide::SellBase => SwapAction::sell_base(pair, amount),
+            OrderSide::BuyBase => SwapAction::buy_base(pair, amount),
+        }
+    }
+
+    pub fn describe(&self, pair: &TradingPair) -> String {
+        let (verb, symbol) = match self.order.side {
+            OrderSide::SellBase => ("sell", &pair.base.symbol),
+            OrderSide::BuyBase => ("buy with", &pair.quote.symbol),
+        };
+        let trigger = match self.order.kind {
+            OrderKind::Limit { price } => format!("limit @ {price:.4}"),
+            OrderKind::StopLoss { price } => format!("stop-loss @ {price:.4}"),
+            OrderKind::TakeProfit { price } => format!("take-profit @ {price:.4}"),
+            OrderKind::TrailingStop { trail, .. } => format!("trailing stop {:.2}%", trail * 100.0),
+        };
+        format!(
+            "#{} {trigger}, {verb} {:.4} {symbol}",
+            self.id, self.order.amount
+        )
+    }
+}
+
+/// Resting orders of one strategy, triggered by its pair price.
+#[derive(Debug, Default)]
+pub struct OrderBook {
+    orders: Vec<RestingOrder>,
+    next_id: u64,
+    /// Protective orders placed after every fill that buys base.
+    exits: Option<ExitConfig>,
+}
+
+impl OrderBook {
+    pub fn new(orders: &[Order], exits: Option<ExitConfig>) -> Self {
+        let mut book = Self {
+            exits,
+            ..Self::default()
+        };
+        for order in orders {
+            book.place(order.clone());
+        }
+        book
+    }
+
+    pub fn orders(&self) -> &[RestingOrder] {
+        &self.orders
+    }
+
+    pub fn place(&mut self, order: Order) {
+        self.next_id += 1;
+        self.orders.push(RestingOrder {
+            id: self.next_id,
+            order,
+        });
+    }
+
+    /// Returns copies of the orders `price` triggers, at most one per group.
+    /// They stay on the book until `remove_dispatched` takes them off, so an
+    /// order that cannot trade yet fires again on a later price.
+    pub fn trigger(&mut self, price: f64) -> Vec<RestingOrder> {
+        let mut fired: Vec<RestingOrder> = Vec::new();
+        for order in &mut self.orders {
+            let in_fired_group = order.order.group.is_some()
+                && fired
+                    .iter()
+                    .any(|other| other.order.group == order.order.group);
+            if order.update(price) && !in_fired_group {
+                fired.push(order.clone());
+            }
+        }
+        fired
+    }
+
+    /// Removes an order that has become a swap, cancelling the rest of its
+    /// group.
+    pub fn remove_dispatched(&mut self, id: u64) {
+        let Some(position) = self.orders.iter().position(|order| order.id == id) else {
+            return;
+        };
+        let order = self.orders.remove(position);
+        if order.order.group.is_some() {
+            self.orders
+                .retain(|other| other.order.group != order.order.group);
+        }
+    }
+
+    /// Places the configured exits for base bought at `price`.
+    pub fn attach_exits(&mut self, base_bought: f64, price: f64) {
+        let Some(exits) = self.exits.clone() else {
+            return;
+        };
+        let group = format!("exit-{}", self.next_id + 1);
+        let kinds = [
+            exits.stop_loss.map(|offset| OrderKind::StopLoss {
+                price: price * (1.0 - offset),
+            }),
+            exits.take_profit.map(|offset| OrderKind::TakeProfit {
+                price: price * (1.0 + offset),
+            }),
+            exits.trailing_stop.map(|trail| OrderKind::TrailingStop {
+                trail,
+                best: Some(price),
+            }),
+        ];
+        for kind in kinds.into_iter().flatten() {
+            self.place(Order {
+                side: OrderSide::SellBase,
+                kind,
+                amount: base_bought,
+                group: Some(group.clone()),
+            });
+        }
+    }
+
+    pub fn snapshot(&self) -> Value {
+        json!(self.orders)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(side: OrderSide, kind: OrderKind, group: Option<&str>) -> Order {
+        Order {
+            side,
+            kind,
+            amount: 1.0,
+            group: group.map(str::to_string),
+        }
+    }
+
+    fn ids(orders: &[RestingOrder]) -> Vec<u64> {
+        orders.iter().map(|order| order.id).collect()
+    }
+
+    #[test]
+    fn limits_and_stops_trigger_on_their_side_of_the_price() {
+        let mut book = OrderBook::new(
+            &[
+                order(OrderSide::SellBase, OrderKind::Limit { price: 110.0 }, None),
+                order(OrderSide::BuyBase, OrderKind::Limit { price: 90.0 }, None),
+                order(
+                    OrderSide::SellBase,
+                    OrderKind::StopLoss { price: 95.0 },
+                    None,
+                ),
+                order(
+                    OrderSide::BuyBase,
+                    OrderKind::StopLoss { price: 105.0 },
+                    None,
+                ),
+            ],
+            None,
+        );
+        assert!(book.trigger(100.0).is_empty());
+        assert_eq!(ids(&book.trigger(110.0)), [1, 4]);
+        assert_eq!(ids(&book.trigger(90.0)), [2, 3]);
+    }
+
+    #[test]
+    fn trailing_stop_follows_the_best_price() {
+        let kind = OrderKind::TrailingStop {
+            trail: 0.1,
+            best: None,
+        };
+        let mut book = OrderBook::new(&[order(OrderSide::SellBase, kind, None)], None);
+        for price in [100.0, 120.0, 109.0] {
+            assert!(book.trigger(price).is_empty(), "{price}");
+        }
+        assert_eq!(ids(&book.trigger(108.0)), [1]);
+    }
+
+    #[test]
+    fn fired_orders_rest_until_dispatched() {
+        let mut book = OrderBook::new(
+            &[order(
+                OrderSide::SellBase,
+                OrderKind::Limit { price: 110.0 },
+                None,
+            )],
+            None,
+        );
+        assert_eq!(ids(&book.trigger(120.0)), [1]);
+        assert_eq!(ids(&book.trigger(120.0)), [1]);
+        book.remove_dispatched(1);
+        assert!(book.orders().is_empty());
+    }
+
+    #[test]
+    fn one_order_per_group_fires_and_its_dispatch_cancels_the_rest() {
+        let mut book = OrderBook::new(
+            &[
+                order(
+                    OrderSide::SellBase,
+                    OrderKind::Limit { price: 110.0 },
+                    Some("oco"),
+                ),
+                order(
+                    OrderSide::SellBase,
+                    OrderKind::Limit { price: 115.0 },
+                    Some("oco"),
+                ),
+                order(OrderSide::SellBase, OrderKind::Limit { price: 130.0 }, None),
+            ],
+            None,
+        );
+        assert_eq!(ids(&book.trigger(120.0)), [1]);
+        book.remove_dispatched(1);
+        assert_eq!(ids(book.orders()), [3]);
+    }
+
+    #[test]
+    fn exits_bracket_the_fill_price_as_one_group() {
+        let exits = ExitConfig {
+            stop_loss: Some(0.05),
+            take_profit: Some(0.1),
+            trailing_stop: None,
+        };
+        let mut book = OrderBook::new(&[], Some(exits));
+        book.attach_exits(2.0, 100.0);
+        assert_eq!(book.orders().len(), 2);
+        assert!(book.trigger(100.0).is_empty());
+        let fired = book.trigger(94.0);
+        assert_eq!(ids(&fired), [1]);
+        assert_eq!(fired[0].order.amount, 2.0);
+        book.remove_dispatched(1);
+        assert!(book.orders().is_empty());
+    }
+}
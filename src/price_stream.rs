@@ -1,4 +1,4 @@
-use crate::config::PriceFeed;
+use crate::config::{Config, normalize_feed_id};
 use crate::jupiter::JupiterClient;
 use crate::model::{AppState, HermesResponse, ParsedPriceData, PriceInfo};
 use crate::simulation::apply_price_update;
@@ -8,23 +8,24 @@ use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
 
-pub async fn run(state: AppState, jupiter: Option<Arc<JupiterClient>>, feed: PriceFeed) {
+pub async fn run(state: AppState, config: Arc<Config>, jupiter: Option<Arc<JupiterClient>>) {
+    let reconnect_delay = Duration::from_secs(config.hermes.reconnect_delay_secs);
     loop {
-        if let Err(err) = stream_prices(state.clone(), jupiter.clone(), feed).await {
+        if let Err(err) = stream_prices(state.clone(), config.clone(), jupiter.clone()).await {
             eprintln!("price stream error: {err:?}");
-            sleep(Duration::from_secs(3)).await;
+            sleep(reconnect_delay).await;
         }
     }
 }
 
 async fn stream_prices(
     state: AppState,
+    config: Arc<Config>,
     jupiter: Option<Arc<JupiterClient>>,
-    feed: PriceFeed,
 ) -> Result<()> {
     let client = reqwest::Client::new();
     let response = client
-        .get(feed.stream_url())
+        .get(config.hermes.stream_url())
         .header("Accept", "text/event-stream")
         .send()
         .await?;
@@ -44,12 +45,11 @@ async fn stream_prices(
                 continue;
             }
 
-            if let Some(payload) = line.strip_prefix("data:") {
-                if let Err(err) =
-                    handle_payload(payload.trim(), state.clone(), jupiter.clone()).await
-                {
-                    eprintln!("failed to handle price payload: {err:?}");
-                }
+            if let Some(payload) = line.strip_prefix("data:")
+                && let Err(err) =
+                    handle_payload(payload.trim(), &state, &config, jupiter.clone()).await
+            {
+                eprintln!("failed to handle price payload: {err:?}");
             }
         }
     }
@@ -59,14 +59,16 @@ async fn stream_prices(
 
 async fn handle_payload(
     payload: &str,
-    state: AppState,
+    state: &AppState,
+    config: &Config,
     jupiter: Option<Arc<JupiterClient>>,
 ) -> Result<()> {
     let parsed: HermesResponse = serde_json::from_str(payload)?;
 
     for price in parsed.parsed {
+        let feed_id = normalize_feed_id(&price.id);
         if let Some(price_info) = to_price_info(price.price) {
-            apply_price_update(&state, price_info, jupiter.clone()).await;
+            apply_price_update(state, config, &feed_id, price_info, jupiter.clone()).await;
         }
     }
 
@@ -0,0 +1,263 @@
+use crate::config::RiskConfig;
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+
+/// Enforces a strategy's `risk` limits between its decision and execution.
+/// A breached limit halts the strategy, either for good or until the limit's
+/// window has passed, and keeps the reason for the dashboard; an oversized
+/// trade is only rejected. Nothing is printed here, since backtests run many
+/// managers in parallel; the live loop reports `last_event`.
+#[derive(Debug)]
+pub struct RiskManager {
+    /// Label of the guarded strategy, for logging.
+    label: String,
+    config: RiskConfig,
+    peak_equity: f64,
+    /// UTC day the daily loss limit is measured over, and the equity it
+    /// started with.
+    day: Option<(NaiveDate, f64)>,
+    /// Times of the trades filled in the last hour.
+    recent_trades: VecDeque<DateTime<Utc>>,
+    halt: Option<Halt>,
+    /// Trades rejected for exceeding `max_notional_per_trade`.
+    rejected_trades: u64,
+    /// Number of halts and rejections so far, and a description of the
+    /// latest one.
+    events: u64,
+    last_event: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Halt {
+    pub reason: String,
+    pub since: DateTime<Utc>,
+    /// When the strategy resumes; `None` halts it for good.
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl RiskManager {
+    pub fn new(label: &str, config: &RiskConfig) -> Self {
+        Self {
+            label: label.to_string(),
+            config: config.clone(),
+            peak_equity: 0.0,
+            day: None,
+            recent_trades: VecDeque::new(),
+            halt: None,
+            rejected_trades: 0,
+            events: 0,
+            last_event: None,
+        }
+    }
+
+    /// Tracks equity and halts the strategy on a drawdown or daily loss
+    /// breach. Returns whether the strategy may trade at `now`.
+    pub fn observe(&mut self, equity: f64, now: DateTime<Utc>) -> bool {
+        if let Some(halt) = &self.halt
+            && halt.until.is_some_and(|until| now >= until)
+        {
+            self.halt = None;
+        }
+
+        self.peak_equity = self.peak_equity.max(equity);
+        let today = now.date_naive();
+        let day_start = match self.day {
+            Some((day, start)) if day == today => start,
+            _ => {
+                self.day = Some((today, equity));
+                equity
+            }
+        };
+
+        if self.halt.is_none() {
+            if let Some(limit) = self.config.max_drawdown
+                && self.peak_equity > 0.0
+            {
+                let drawdown = 1.0 - equity / self.peak_equity;
+                if drawdown >= limit {
+                    self.trip(
+                        format!(
+                            "drawdown {:.2}% reached the {:.2}% limit",
+                            drawdown * 100.0,
+                            limit * 100.0
+                        ),
+                        now,
+                        None,
+                    );
+                }
+            }
+            if let Some(limit) = self.config.max_daily_loss
+                && day_start > 0.0
+            {
+                let loss = 1.0 - equity / day_start;
+                if loss >= limit {
+                    let next_day = today.succ_opt().and_then(|day| day.and_hms_opt(0, 0, 0));
+                    self.trip(
+                        format!(
+                            "daily loss {:.2}% reached the {:.2}% limit",
+                            loss * 100.0,
+                            limit * 100.0
+                        ),
+                        now,
+                        next_day.map(|midnight| midnight.and_utc()),
+                    );
+                }
+            }
+        }
+        self.halt.is_none()
+    }
+
+    /// Checks a trade of `notional` quote against the per-trade limits.
+    pub fn approve(&mut self, notional: f64, now: DateTime<Utc>) -> bool {
+        if self.halt.is_some() {
+            return false;
+        }
+        if let Some(limit) = self.config.max_notional_per_trade
+            && notional > limit
+        {
+            self.rejected_trades += 1;
+            self.record(format!(
+                "rejected swap by `{}`: trade of {notional:.4} quote exceeds the {limit:.4} limit",
+                self.label
+            ));
+            return false;
+        }
+        if let Some(limit) = self.config.max_trades_per_hour {
+            let hour = TimeDelta::hours(1);
+            while self
+                .recent_trades
+                .front()
+                .is_some_and(|time| now - *time >= hour)
+            {
+                self.recent_trades.pop_front();
+            }
+            if self.recent_trades.len() >= limit {
+                let until = self.recent_trades.front().map(|oldest| *oldest + hour);
+                self.trip(format!("more than {limit} trades in an hour"), now, until);
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Counts a filled trade towards the hourly limit.
+    pub fn record_trade(&mut self, now: DateTime<Utc>) {
+        if self.config.max_trades_per_hour.is_some() {
+            self.recent_trades.push_back(now);
+        }
+    }
+
+    pub fn halt(&self) -> Option<&Halt> {
+        self.halt.as_ref()
+    }
+
+    /// Number of halts and rejections so far.
+    pub fn events(&self) -> u64 {
+        self.events
+    }
+
+    pub fn last_event(&self) -> Option<&str> {
+        self.last_event.as_deref()
+    }
+
+    pub fn status(&self) -> Value {
+        match &self.halt {
+            Some(halt) => json!({
+                "halted": true,
+                "reason": halt.reason,
+                "since": halt.since.to_rfc3339(),
+                "until": halt.until.map(|until| until.to_rfc3339()),
+                "rejected_trades": self.rejected_trades,
+            }),
+            None => json!({ "halted": false, "rejected_trades": self.rejected_trades }),
+        }
+    }
+
+    fn trip(&mut self, reason: String, now: DateTime<Utc>, until: Option<DateTime<Utc>>) {
+        self.record(format!("halting strategy `{}`: {reason}", self.label));
+        self.halt = Some(Halt {
+            reason,
+            since: now,
+            until,
+        });
+    }
+
+    fn record(&mut self, event: String) {
+        self.events += 1;
+        self.last_event = Some(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minutes(minutes: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::UNIX_EPOCH + TimeDelta::minutes(minutes)
+    }
+
+    fn manager(config: RiskConfig) -> RiskManager {
+        RiskManager::new("test", &config)
+    }
+
+    #[test]
+    fn oversized_trade_is_rejected_without_halting() {
+        let mut risk = manager(RiskConfig {
+            max_notional_per_trade: Some(100.0),
+            ..RiskConfig::default()
+        });
+        assert!(!risk.approve(150.0, minutes(0)));
+        assert!(risk.halt().is_none());
+        assert!(risk.approve(50.0, minutes(1)));
+        assert_eq!(risk.events(), 1);
+        assert_eq!(risk.status()["rejected_trades"], 1);
+    }
+
+    #[test]
+    fn hourly_limit_counts_filled_trades_only() {
+        let mut risk = manager(RiskConfig {
+            max_trades_per_hour: Some(2),
+            ..RiskConfig::default()
+        });
+        // Approved trades that never fill leave the quota alone.
+        for minute in 0..5 {
+            assert!(risk.approve(1.0, minutes(minute)));
+        }
+        risk.record_trade(minutes(10));
+        risk.record_trade(minutes(20));
+        assert!(!risk.approve(1.0, minutes(30)));
+        let halt = risk.halt().unwrap();
+        assert_eq!(halt.until, Some(minutes(70)));
+
+        assert!(risk.observe(100.0, minutes(70)));
+        assert!(risk.approve(1.0, minutes(70)));
+    }
+
+    #[test]
+    fn drawdown_halts_for_good() {
+        let mut risk = manager(RiskConfig {
+            max_drawdown: Some(0.2),
+            ..RiskConfig::default()
+        });
+        assert!(risk.observe(100.0, minutes(0)));
+        assert!(risk.observe(85.0, minutes(1)));
+        assert!(!risk.observe(79.0, minutes(2)));
+        assert!(!risk.observe(120.0, minutes(3)));
+        assert_eq!(risk.halt().unwrap().until, None);
+        assert!(!risk.approve(1.0, minutes(3)));
+    }
+
+    #[test]
+    fn daily_loss_halts_until_the_next_utc_day() {
+        let mut risk = manager(RiskConfig {
+            max_daily_loss: Some(0.05),
+            ..RiskConfig::default()
+        });
+        assert!(risk.observe(100.0, minutes(60)));
+        assert!(!risk.observe(94.0, minutes(120)));
+        assert_eq!(risk.halt().unwrap().until, Some(minutes(24 * 60)));
+        assert!(!risk.observe(94.0, minutes(24 * 60 - 1)));
+        assert!(risk.observe(94.0, minutes(24 * 60)));
+    }
+}
@@ -0,0 +1,18 @@
+use crate::config::Config;
+use crate::jupiter::JupiterClient;
+use crate::model::AppState;
+use crate::simulation::apply_schedule;
+use chrono::Utc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{MissedTickBehavior, interval};
+
+/// Drives time-based strategy decisions independently of price ticks.
+pub async fn run(state: AppState, config: Arc<Config>, jupiter: Option<Arc<JupiterClient>>) {
+    let mut ticker = interval(Duration::from_secs(config.simulation.scheduler_tick_secs));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        ticker.tick().await;
+        apply_schedule(&state, &config, Utc::now(), jupiter.clone()).await;
+    }
+}
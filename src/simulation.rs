@@ -1,10 +1,14 @@
-use crate::config::{MAX_HISTORY_ENTRIES, SOL_DECIMALS, SOL_MINT, USDC_DECIMALS, USDC_MINT};
+use crate::config::Config;
+use crate::fees::{FeeModel, Pricing};
+use crate::impact::ImpactModel;
 use crate::jupiter::JupiterClient;
-use crate::model::{
-    AppState, PriceInfo, StrategyData, StrategyId, StrategyState, SwapDirection, SwapRecord, Token,
-};
-use anyhow::{Error, Result, anyhow};
-use chrono::{DateTime, Utc};
+use crate::model::{AppData, AppState, PriceInfo, StrategyData, SwapRecord, WalletState};
+use crate::orders::{OrderSide, RestingOrder};
+use crate::strategies::{Fill, Strategy, SwapAction, TickContext};
+use crate::tokens::{Token, TradingPair};
+use anyhow::{Error, Result, bail};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::HashMap;
 use std::sync::{
     Arc,
     atomic::{AtomicBool, Ordering},
@@ -12,306 +16,599 @@ use std::sync::{
 
 static JUPITER_WARNING_EMITTED: AtomicBool = AtomicBool::new(false);
 
-const TREND_THRESHOLD: f64 = 0.002;
-const TREND_SELL_SOL_AMOUNT: f64 = 0.15;
-const TREND_BUY_USDC_AMOUNT: f64 = 25.0;
-
-const RANGE_THRESHOLD: f64 = 0.003;
-const RANGE_SELL_SOL_AMOUNT: f64 = 0.1;
-const RANGE_BUY_USDC_AMOUNT: f64 = 20.0;
-
-const MIN_SOL_AMOUNT: f64 = 1e-6;
-const MIN_USDC_AMOUNT: f64 = 0.01;
-
 pub async fn apply_price_update(
     state: &AppState,
+    config: &Config,
+    feed_id: &str,
     price_info: PriceInfo,
     jupiter: Option<Arc<JupiterClient>>,
 ) {
-    if price_info.value <= 0.0 {
-        return;
+    let ready = {
+        let mut data = state.lock().await;
+        let events = risk_events(&data);
+        let ready = plan_price_update(&mut data, feed_id, price_info, Utc::now());
+        log_risk_events(&data, &events);
+        ready
+    };
+    execute_pending(state, config, ready, jupiter).await;
+}
+
+/// Runs every strategy's scheduled hook at `now`.
+pub async fn apply_schedule(
+    state: &AppState,
+    config: &Config,
+    now: DateTime<Utc>,
+    jupiter: Option<Arc<JupiterClient>>,
+) {
+    let ready = {
+        let mut data = state.lock().await;
+        let events = risk_events(&data);
+        let ready = plan_schedule(&mut data, now);
+        log_risk_events(&data, &events);
+        ready
+    };
+    execute_pending(state, config, ready, jupiter).await;
+}
+
+/// Number of risk events per strategy so far.
+fn risk_events(data: &AppData) -> Vec<u64> {
+    data.strategies
+        .iter()
+        .map(|strategy| strategy.risk.as_ref().map_or(0, |risk| risk.events()))
+        .collect()
+}
+
+/// Prints the latest risk event of every strategy that has had one since
+/// `before` was taken.
+fn log_risk_events(data: &AppData, before: &[u64]) {
+    for (strategy, &before) in data.strategies.iter().zip(before) {
+        if let Some(risk) = &strategy.risk
+            && risk.events() > before
+            && let Some(event) = risk.last_event()
+        {
+            eprintln!("{event}");
+        }
     }
+}
 
-    let mut guard = state.lock().await;
-    guard.latest_price = Some(price_info.clone());
+/// Records a price and lets the strategies trading on its feed decide at
+/// `now`. Returns the swaps to fill now.
+pub fn plan_price_update(
+    data: &mut AppData,
+    feed_id: &str,
+    price_info: PriceInfo,
+    now: DateTime<Utc>,
+) -> Vec<PendingAction> {
+    if price_info.value <= 0.0 {
+        return Vec::new();
+    }
+    data.latest_prices
+        .insert(feed_id.to_string(), price_info.clone());
 
     let mut pending = Vec::new();
-    for (index, strategy) in guard.strategies.iter_mut().enumerate() {
-        if let Some(action) = determine_action(index, strategy, &price_info) {
+    for (index, strategy) in data.strategies.iter_mut().enumerate() {
+        if strategy.pair.base.feed_id.as_deref() != Some(feed_id) {
+            continue;
+        }
+        let Some(pair_price) = pair_price(&strategy.pair, &price_info, &data.latest_prices) else {
+            continue;
+        };
+        if let Some(sizer) = &mut strategy.sizer {
+            sizer.observe(&pair_price);
+        }
+        let base = strategy.wallet.balance(&strategy.pair.base);
+        let quote = strategy.wallet.balance(&strategy.pair.quote);
+        strategy
+            .performance
+            .observe(base, quote, pair_price.value, now);
+        strategy
+            .benchmarks
+            .observe(base * pair_price.value + quote, pair_price.value, now);
+        // Wallets only change on fill, so while a swap is in flight the
+        // strategy is not consulted and triggered orders stay on the book;
+        // level crossings and signals are picked up once the swap lands.
+        let fired = strategy.orders.trigger(pair_price.value);
+        if awaiting_fill(&data.in_flight, index) {
+            continue;
+        }
+        // One swap per strategy and tick, so every swap is sized against
+        // the wallet it will fill from. A fired order takes the tick.
+        let order_swap = fired.into_iter().find_map(|order| {
+            plan_order(
+                index,
+                strategy,
+                &pair_price,
+                &data.latest_prices,
+                &data.fees,
+                now,
+                order,
+            )
+        });
+        if let Some(action) = order_swap {
+            if let Some(order) = &action.order {
+                strategy.orders.remove_dispatched(order.id);
+            }
             pending.push(action);
+            continue;
         }
+        pending.extend(plan_action(
+            index,
+            strategy,
+            pair_price,
+            &data.latest_prices,
+            &data.fees,
+            now,
+            |strategy, ctx| strategy.on_tick(ctx),
+        ));
     }
-    drop(guard);
+    dispatch(data, pending, now)
+}
 
-    for action in pending {
-        let execution = execute_action(jupiter.clone(), &price_info, &action.action).await;
-
-        let mut guard = state.lock().await;
-        if let Some(strategy) = guard.strategies.get_mut(action.strategy_index) {
-            apply_wallet_updates(strategy, &action, &execution);
-
-            let record = SwapRecord {
-                timestamp: current_timestamp(),
-                direction: action.action.direction_label().to_string(),
-                price: price_info.value,
-                amount_in: execution.amount_in,
-                amount_out: execution.amount_out,
-                strategy: action.strategy_id,
-                input_token: execution.input_token,
-                output_token: execution.output_token,
-                gas_lamports: execution.gas_lamports,
-                price_impact_pct: execution.price_impact_pct,
-            };
+/// Feeds a price to the strategies without trading, so indicators and
+/// sizers start warm. Decisions and new orders are dropped, and nothing is
+/// recorded in the performance, benchmark or risk trackers.
+pub fn warm_up(data: &mut AppData, feed_id: &str, price_info: PriceInfo) {
+    if price_info.value <= 0.0 {
+        return;
+    }
+    data.latest_prices
+        .insert(feed_id.to_string(), price_info.clone());
 
-            guard.history.push(record);
-            if guard.history.len() > MAX_HISTORY_ENTRIES {
-                let excess = guard.history.len() - MAX_HISTORY_ENTRIES;
-                guard.history.drain(0..excess);
-            }
+    for strategy in &mut data.strategies {
+        if strategy.pair.base.feed_id.as_deref() != Some(feed_id) {
+            continue;
         }
+        let Some(pair_price) = pair_price(&strategy.pair, &price_info, &data.latest_prices) else {
+            continue;
+        };
+        if let Some(sizer) = &mut strategy.sizer {
+            sizer.observe(&pair_price);
+        }
+        let ctx = TickContext {
+            pair: &strategy.pair,
+            price: &pair_price,
+            wallet: &strategy.wallet,
+        };
+        strategy.strategy.on_tick(&ctx);
+        strategy.strategy.take_orders();
     }
 }
 
-pub fn publish_time_to_string(ts: Option<i64>) -> String {
-    ts.and_then(|value| DateTime::<Utc>::from_timestamp(value, 0).map(format_publish_time))
-        .unwrap_or_else(|| "unknown".to_string())
+/// Runs every strategy's scheduled hook at `now`, priced at the latest
+/// oracle prices, and returns the swaps to fill now. Strategies whose pair
+/// has not been priced yet are skipped, as are strategies waiting on a
+/// delayed fill.
+pub fn plan_schedule(data: &mut AppData, now: DateTime<Utc>) -> Vec<PendingAction> {
+    let mut pending = Vec::new();
+    for (index, strategy) in data.strategies.iter_mut().enumerate() {
+        let Some(pair_price) = strategy
+            .pair
+            .base
+            .feed_id
+            .as_deref()
+            .and_then(|feed_id| data.latest_prices.get(feed_id))
+            .and_then(|base_usd| pair_price(&strategy.pair, base_usd, &data.latest_prices))
+        else {
+            continue;
+        };
+        if awaiting_fill(&data.in_flight, index) {
+            continue;
+        }
+        pending.extend(plan_action(
+            index,
+            strategy,
+            pair_price,
+            &data.latest_prices,
+            &data.fees,
+            now,
+            |strategy, ctx| strategy.on_schedule(ctx, now),
+        ));
+    }
+    dispatch(data, pending, now)
 }
 
-fn format_publish_time(dt: DateTime<Utc>) -> String {
-    dt.format("%Y-%m-%d %H:%M:%S UTC").to_string()
-}
+/// Holds back the swaps the latency model delays and returns the ones to
+/// fill now: undelayed decisions, plus in-flight swaps that have come due,
+/// repriced at the latest prices.
+fn dispatch(
+    data: &mut AppData,
+    decided: Vec<PendingAction>,
+    now: DateTime<Utc>,
+) -> Vec<PendingAction> {
+    let (mut ready, waiting): (Vec<_>, Vec<_>) = data
+        .in_flight
+        .drain(..)
+        .partition(|action| action.due_at().is_some_and(|due| due <= now));
+    data.in_flight = waiting;
+    for action in &mut ready {
+        if let Some(strategy) = data.strategies.get(action.strategy_index) {
+            reprice(action, &strategy.pair, &data.latest_prices);
+        }
+    }
 
-#[derive(Debug)]
-struct PendingAction {
-    strategy_index: usize,
-    strategy_id: StrategyId,
-    action: SwapAction,
-    post_update: Option<StrategyPostUpdate>,
+    for mut action in decided {
+        match data.latency.sample() {
+            Some(delay) => {
+                action.delay = Some(delay);
+                data.in_flight.push(action);
+            }
+            None => ready.push(action),
+        }
+    }
+    ready
 }
 
-#[derive(Debug)]
-enum StrategyPostUpdate {
-    AlternatingNext(SwapDirection),
+fn awaiting_fill(in_flight: &[PendingAction], strategy_index: usize) -> bool {
+    in_flight
+        .iter()
+        .any(|action| action.strategy_index == strategy_index)
 }
 
-#[derive(Debug)]
-enum SwapAction {
-    SolToUsdc { amount_sol: f64 },
-    UsdcToSol { amount_usdc: f64 },
+/// Moves a delayed swap to the latest pair price and oracle rate.
+fn reprice(
+    action: &mut PendingAction,
+    pair: &TradingPair,
+    latest_prices: &HashMap<String, PriceInfo>,
+) {
+    let latest = pair
+        .base
+        .feed_id
+        .as_deref()
+        .and_then(|feed_id| latest_prices.get(feed_id))
+        .and_then(|base_usd| pair_price(pair, base_usd, latest_prices));
+    if let Some(price) = latest {
+        action.price = price;
+    }
+    if let Some(rate) = exchange_rate(&action.action, latest_prices) {
+        action.rate = rate;
+    }
 }
 
-impl SwapAction {
-    fn input_token(&self) -> Token {
-        match self {
-            SwapAction::SolToUsdc { .. } => Token::Sol,
-            SwapAction::UsdcToSol { .. } => Token::Usdc,
+/// Asks a strategy for a decision at the given pair price, resizes it if the
+/// strategy has a sizer, checks it against the strategy's risk limits and
+/// prices the resulting swap. Halted strategies are not consulted.
+fn plan_action(
+    index: usize,
+    strategy: &mut StrategyData,
+    pair_price: PriceInfo,
+    latest_prices: &HashMap<String, PriceInfo>,
+    fees: &FeeModel,
+    now: DateTime<Utc>,
+    decide: impl FnOnce(&mut dyn Strategy, &TickContext) -> Option<SwapAction>,
+) -> Option<PendingAction> {
+    let pair = &strategy.pair;
+    if let Some(risk) = &mut strategy.risk {
+        let equity = strategy.wallet.balance(&pair.base) * pair_price.value
+            + strategy.wallet.balance(&pair.quote);
+        if !risk.observe(equity, now) {
+            return None;
         }
     }
 
-    fn output_token(&self) -> Token {
-        match self {
-            SwapAction::SolToUsdc { .. } => Token::Usdc,
-            SwapAction::UsdcToSol { .. } => Token::Sol,
+    let ctx = TickContext {
+        pair,
+        price: &pair_price,
+        wallet: &strategy.wallet,
+    };
+    let action = decide(strategy.strategy.as_mut(), &ctx);
+    for order in strategy.strategy.take_orders() {
+        strategy.orders.place(order);
+    }
+    let mut action = action?;
+    let mut indicators = strategy.strategy.indicators();
+    if let Some(sizer) = &strategy.sizer {
+        action = sizer.size(action, &ctx)?;
+        if let Some(vol) = sizer.realized_vol() {
+            indicators.push(("realized_vol".to_string(), vol));
         }
     }
-
-    fn direction_label(&self) -> &'static str {
-        match self {
-            SwapAction::SolToUsdc { .. } => "SOL → USDC",
-            SwapAction::UsdcToSol { .. } => "USDC → SOL",
+    let action = reserve_network_fee(action, &strategy.wallet, fees)?;
+    if let Some(risk) = &mut strategy.risk {
+        let notional = if action.input == pair.base {
+            action.amount_in * pair_price.value
+        } else {
+            action.amount_in
+        };
+        if !risk.approve(notional, now) {
+            return None;
         }
     }
+    let rate = exchange_rate(&action, latest_prices)?;
+    Some(PendingAction {
+        strategy_index: index,
+        decision_price: pair_price.value,
+        price: pair_price,
+        rate,
+        indicators,
+        action,
+        order: None,
+        decided_at: now,
+        delay: None,
+    })
 }
 
-struct SwapExecution {
-    amount_in: f64,
-    amount_out: f64,
-    input_token: Token,
-    output_token: Token,
-    gas_lamports: Option<u64>,
-    price_impact_pct: Option<f64>,
-}
-
-fn determine_action(
+/// Turns a triggered resting order into a market swap. Orders rest outside
+/// the sizer and risk limits, so protective exits still fire for a halted
+/// strategy.
+fn plan_order(
     index: usize,
-    strategy: &mut StrategyData,
-    price: &PriceInfo,
+    strategy: &StrategyData,
+    pair_price: &PriceInfo,
+    latest_prices: &HashMap<String, PriceInfo>,
+    fees: &FeeModel,
+    now: DateTime<Utc>,
+    order: RestingOrder,
 ) -> Option<PendingAction> {
-    let strategy_id = strategy.id;
-    match &mut strategy.state {
-        StrategyState::Alternating { next_swap } => match next_swap {
-            SwapDirection::ToUsdc => {
-                if strategy.wallet.sol <= MIN_SOL_AMOUNT {
-                    return None;
-                }
-                Some(PendingAction {
-                    strategy_index: index,
-                    strategy_id,
-                    action: SwapAction::SolToUsdc {
-                        amount_sol: strategy.wallet.sol,
-                    },
-                    post_update: Some(StrategyPostUpdate::AlternatingNext(SwapDirection::ToSol)),
-                })
-            }
-            SwapDirection::ToSol => {
-                if strategy.wallet.usdc <= MIN_USDC_AMOUNT {
-                    return None;
-                }
-                Some(PendingAction {
-                    strategy_index: index,
-                    strategy_id,
-                    action: SwapAction::UsdcToSol {
-                        amount_usdc: strategy.wallet.usdc,
-                    },
-                    post_update: Some(StrategyPostUpdate::AlternatingNext(SwapDirection::ToUsdc)),
-                })
-            }
-        },
-        StrategyState::TrendFollow { last_price } => {
-            let previous = *last_price;
-            *last_price = Some(price.value);
+    let pair = &strategy.pair;
+    let input = match order.order.side {
+        OrderSide::SellBase => &pair.base,
+        OrderSide::BuyBase => &pair.quote,
+    };
+    let action = order.to_action(pair, strategy.wallet.balance(input));
+    let action = reserve_network_fee(action, &strategy.wallet, fees)?;
+    let rate = exchange_rate(&action, latest_prices)?;
+    Some(PendingAction {
+        strategy_index: index,
+        decision_price: pair_price.value,
+        price: pair_price.clone(),
+        rate,
+        indicators: strategy.strategy.indicators(),
+        action,
+        order: Some(order),
+        decided_at: now,
+        delay: None,
+    })
+}
 
-            let Some(prev) = previous else {
-                return None;
-            };
+/// Trims a swap out of SOL so the wallet keeps enough to pay its network
+/// fee. Returns `None` if too little is left to trade.
+fn reserve_network_fee(
+    mut action: SwapAction,
+    wallet: &WalletState,
+    fees: &FeeModel,
+) -> Option<SwapAction> {
+    if let Some((sol, lamports)) = fees.network_reserve()
+        && action.input == *sol
+    {
+        let spendable = sol.to_whole(wallet.units(sol).saturating_sub(lamports));
+        action.amount_in = action.amount_in.min(spendable);
+    }
+    (action.amount_in > action.input.min_trade_amount).then_some(action)
+}
 
-            let change = (price.value - prev) / prev;
-            if change >= TREND_THRESHOLD && strategy.wallet.sol > MIN_SOL_AMOUNT {
-                let amount = strategy
-                    .wallet
-                    .sol
-                    .min(TREND_SELL_SOL_AMOUNT)
-                    .max(MIN_SOL_AMOUNT);
-                return Some(PendingAction {
-                    strategy_index: index,
-                    strategy_id,
-                    action: SwapAction::SolToUsdc { amount_sol: amount },
-                    post_update: None,
-                });
-            }
+async fn execute_pending(
+    state: &AppState,
+    config: &Config,
+    pending: Vec<PendingAction>,
+    jupiter: Option<Arc<JupiterClient>>,
+) {
+    for action in pending {
+        let quoted = execute_with_jupiter_if_enabled(jupiter.clone(), &action).await;
+        fill(&mut *state.lock().await, config, action, quoted, Utc::now());
+    }
+}
 
-            if change <= -TREND_THRESHOLD && strategy.wallet.usdc > MIN_USDC_AMOUNT {
-                let amount = strategy
-                    .wallet
-                    .usdc
-                    .min(TREND_BUY_USDC_AMOUNT)
-                    .max(MIN_USDC_AMOUNT);
-                return Some(PendingAction {
-                    strategy_index: index,
-                    strategy_id,
-                    action: SwapAction::UsdcToSol {
-                        amount_usdc: amount,
-                    },
-                    post_update: None,
-                });
+/// Fills a swap at `now` and records it. Swaps Jupiter did not quote are
+/// priced locally.
+pub fn fill(
+    data: &mut AppData,
+    config: &Config,
+    action: PendingAction,
+    quoted: Option<SwapExecution>,
+    now: DateTime<Utc>,
+) {
+    let notional_usd = action
+        .action
+        .input
+        .usd_price(&data.latest_prices)
+        .map(|price| price * action.action.amount_in)
+        .unwrap_or(0.0);
+    let mut execution = match quoted {
+        Some(execution) => {
+            if let Some(impact) = execution.price_impact_pct {
+                data.impact.observe(
+                    &execution.input_token,
+                    &execution.output_token,
+                    notional_usd,
+                    impact,
+                );
             }
-
+            execution
+        }
+        None => execute_with_price(&data.impact, action.rate, &action.action, notional_usd),
+    };
+    let mut fees = data.fees.charge(execution.amount_out, &execution.pricing);
+    execution.amount_out -= fees.output_fee();
+    let Some(strategy) = data.strategies.get_mut(action.strategy_index) else {
+        return;
+    };
+    let network_fee = match data.fees.sol() {
+        Some(sol)
+            if pays_network_fee(&strategy.wallet, sol, &execution, fees.network_lamports()) =>
+        {
+            Some((sol, fees.network_lamports()))
+        }
+        _ => {
+            fees.waive_network();
             None
         }
-        StrategyState::RangeTrader { last_price } => {
-            let previous = *last_price;
-            *last_price = Some(price.value);
+    };
+    let realized_profit = match apply_wallet_updates(strategy, &action, &execution, network_fee) {
+        Ok(realized_profit) => realized_profit,
+        Err(err) => {
+            eprintln!("rejected swap by `{}`: {err}", strategy.strategy.label());
+            return;
+        }
+    };
+    if let Some(risk) = &mut strategy.risk
+        && action.order.is_none()
+    {
+        risk.record_trade(now);
+    }
+    let mut direction = format!(
+        "{} → {}",
+        execution.input_token.symbol, execution.output_token.symbol
+    );
+    if let Some(order) = &action.order {
+        direction.push_str(&format!(" ({})", order.describe(&strategy.pair)));
+    }
 
-            let Some(prev) = previous else {
-                return None;
-            };
+    let record = SwapRecord {
+        timestamp: now,
+        direction,
+        price: action.price.value,
+        strategy_index: action.strategy_index,
+        amount_in: execution.amount_in,
+        amount_out: execution.amount_out,
+        strategy: strategy.strategy.label().to_string(),
+        input_token: execution.input_token,
+        output_token: execution.output_token,
+        fees,
+        price_impact_pct: execution.price_impact_pct,
+        indicators: action.indicators,
+        realized_profit,
+        decision_price: action.decision_price,
+        fill_delay_ms: action.delay.map(|delay| delay.num_milliseconds()),
+    };
 
-            if price.value >= prev * (1.0 + RANGE_THRESHOLD) && strategy.wallet.sol > MIN_SOL_AMOUNT
-            {
-                let amount = strategy
-                    .wallet
-                    .sol
-                    .min(RANGE_SELL_SOL_AMOUNT)
-                    .max(MIN_SOL_AMOUNT);
-                return Some(PendingAction {
-                    strategy_index: index,
-                    strategy_id,
-                    action: SwapAction::SolToUsdc { amount_sol: amount },
-                    post_update: None,
-                });
-            }
+    data.history.push(record);
+    let max_entries = config.simulation.max_history_entries;
+    if data.history.len() > max_entries {
+        let excess = data.history.len() - max_entries;
+        data.history.drain(0..excess);
+    }
+}
 
-            if price.value <= prev * (1.0 - RANGE_THRESHOLD)
-                && strategy.wallet.usdc > MIN_USDC_AMOUNT
-            {
-                let amount = strategy
-                    .wallet
-                    .usdc
-                    .min(RANGE_BUY_USDC_AMOUNT)
-                    .max(MIN_USDC_AMOUNT);
-                return Some(PendingAction {
-                    strategy_index: index,
-                    strategy_id,
-                    action: SwapAction::UsdcToSol {
-                        amount_usdc: amount,
-                    },
-                    post_update: None,
-                });
-            }
+/// Whether a swap is charged its network fee: always when it buys SOL,
+/// otherwise only if the SOL the wallet keeps after the swap covers it.
+fn pays_network_fee(
+    wallet: &WalletState,
+    sol: &Token,
+    execution: &SwapExecution,
+    lamports: u64,
+) -> bool {
+    let spent = if execution.input_token == *sol {
+        execution.amount_in
+    } else {
+        0
+    };
+    execution.output_token == *sol || wallet.units(sol).saturating_sub(spent) >= lamports
+}
 
-            None
-        }
+/// Converts a USD price of the pair's base token into quote per base.
+fn pair_price(
+    pair: &TradingPair,
+    base_usd: &PriceInfo,
+    latest_prices: &HashMap<String, PriceInfo>,
+) -> Option<PriceInfo> {
+    let quote_usd = pair.quote.usd_price(latest_prices)?;
+    if quote_usd <= 0.0 {
+        return None;
+    }
+    Some(PriceInfo {
+        value: base_usd.value / quote_usd,
+        publish_time: base_usd.publish_time,
+    })
+}
+
+/// Output tokens per input token implied by the latest oracle prices.
+fn exchange_rate(action: &SwapAction, latest_prices: &HashMap<String, PriceInfo>) -> Option<f64> {
+    let input_usd = action.input.usd_price(latest_prices)?;
+    let output_usd = action.output.usd_price(latest_prices)?;
+    (output_usd > 0.0).then(|| input_usd / output_usd)
+}
+
+pub fn publish_time_to_string(ts: Option<i64>) -> String {
+    ts.and_then(|value| DateTime::<Utc>::from_timestamp(value, 0).map(format_publish_time))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn format_publish_time(dt: DateTime<Utc>) -> String {
+    dt.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+/// Swap decided by a strategy and waiting to be filled.
+#[derive(Debug)]
+pub struct PendingAction {
+    strategy_index: usize,
+    /// Pair price the decision was made on, in quote per base.
+    decision_price: f64,
+    /// Pair price the swap fills at; moves on if the fill is delayed.
+    price: PriceInfo,
+    /// Output tokens received per input token when filled at `price`.
+    rate: f64,
+    indicators: Vec<(String, f64)>,
+    action: SwapAction,
+    /// Resting order that produced the swap, if any.
+    order: Option<RestingOrder>,
+    decided_at: DateTime<Utc>,
+    /// Signal-to-fill delay drawn from the latency model.
+    delay: Option<TimeDelta>,
+}
+
+impl PendingAction {
+    fn due_at(&self) -> Option<DateTime<Utc>> {
+        self.delay.map(|delay| self.decided_at + delay)
     }
 }
 
-async fn execute_action(
+/// Filled swap, with amounts in base units.
+pub struct SwapExecution {
+    amount_in: u64,
+    amount_out: u64,
+    input_token: Token,
+    output_token: Token,
+    pricing: Pricing,
+    price_impact_pct: Option<f64>,
+}
+
+/// Quotes the swap on Jupiter when enabled. `None` means the swap has to be
+/// priced locally.
+async fn execute_with_jupiter_if_enabled(
     jupiter: Option<Arc<JupiterClient>>,
-    price: &PriceInfo,
-    action: &SwapAction,
-) -> SwapExecution {
-    if let Some(client) = jupiter {
-        match execute_with_jupiter(client, action).await {
-            Ok(execution) => return execution,
-            Err(err) => {
-                log_jupiter_warning(&err);
-            }
+    pending: &PendingAction,
+) -> Option<SwapExecution> {
+    let client = jupiter?;
+    match execute_with_jupiter(client, &pending.action).await {
+        Ok(execution) => Some(execution),
+        Err(err) => {
+            log_jupiter_warning(&err);
+            None
         }
     }
-
-    execute_with_price(price, action)
 }
 
 async fn execute_with_jupiter(
     client: Arc<JupiterClient>,
     action: &SwapAction,
 ) -> Result<SwapExecution> {
-    let input_token = action.input_token();
-    let output_token = action.output_token();
-    let (input_mint, input_decimals) = token_details(input_token);
-    let (output_mint, output_decimals) = token_details(output_token);
-
-    let amount_in_requested = match action {
-        SwapAction::SolToUsdc { amount_sol } => *amount_sol,
-        SwapAction::UsdcToSol { amount_usdc } => *amount_usdc,
-    };
+    let input_token = &action.input;
+    let output_token = &action.output;
 
-    let amount_in_base = to_base_units(amount_in_requested, input_decimals)
-        .ok_or_else(|| anyhow!("amount too small to convert to base units"))?;
+    let amount_in_base = input_token.to_base_units(action.amount_in);
+    if amount_in_base == 0 {
+        bail!("amount too small to convert to base units");
+    }
 
     let quote = client
-        .quote_exact_in(input_mint, output_mint, amount_in_base)
+        .quote_exact_in(&input_token.mint, &output_token.mint, amount_in_base)
         .await?;
 
-    let simulation = match client
-        .simulate_swap(&quote, wraps_sol(input_token, output_token))
-        .await
-    {
+    let wraps_sol = input_token.is_wrapped_sol() || output_token.is_wrapped_sol();
+    let simulation = match client.simulate_swap(&quote, wraps_sol).await {
         Ok(result) => Some(result),
         Err(err) => {
-            eprintln!("Jupiter simulation failed, ignoring gas data: {err:?}");
+            eprintln!("Jupiter simulation failed, charging configured network fees: {err:?}");
             None
         }
     };
 
-    let amount_in = from_base_units(quote.in_amount, input_decimals);
-    let amount_out = from_base_units(quote.out_amount, output_decimals);
-
     Ok(SwapExecution {
-        amount_in,
-        amount_out,
-        input_token,
-        output_token,
-        gas_lamports: simulation.and_then(|s| s.gas_lamports),
+        amount_in: quote.in_amount,
+        amount_out: quote.out_amount,
+        input_token: input_token.clone(),
+        output_token: output_token.clone(),
+        pricing: Pricing::Jupiter(simulation),
         price_impact_pct: quote.price_impact_pct,
     })
 }
@@ -322,92 +619,246 @@ fn log_jupiter_warning(err: &Error) {
     }
 }
 
-fn execute_with_price(price: &PriceInfo, action: &SwapAction) -> SwapExecution {
-    match action {
-        SwapAction::SolToUsdc { amount_sol } => {
-            let amount_in = *amount_sol;
-            let amount_out = amount_in * price.value;
-            SwapExecution {
-                amount_in,
-                amount_out,
-                input_token: Token::Sol,
-                output_token: Token::Usdc,
-                gas_lamports: None,
-                price_impact_pct: None,
-            }
-        }
-        SwapAction::UsdcToSol { amount_usdc } => {
-            let amount_in = *amount_usdc;
-            let amount_out = if price.value > 0.0 {
-                amount_in / price.value
-            } else {
-                0.0
-            };
-            SwapExecution {
-                amount_in,
-                amount_out,
-                input_token: Token::Usdc,
-                output_token: Token::Sol,
-                gas_lamports: None,
-                price_impact_pct: None,
-            }
-        }
+/// Fills at the oracle rate less the impact model's estimate for the size.
+fn execute_with_price(
+    impact: &ImpactModel,
+    rate: f64,
+    action: &SwapAction,
+    notional_usd: f64,
+) -> SwapExecution {
+    let amount_in = action.input.to_base_units(action.amount_in);
+    let price_impact_pct = impact.impact(&action.input, &action.output, notional_usd);
+    let amount_out = if rate.is_finite() && rate > 0.0 {
+        let whole_in = action.input.to_whole(amount_in);
+        action
+            .output
+            .to_base_units(whole_in * rate * (1.0 - price_impact_pct.unwrap_or(0.0)))
+    } else {
+        0
+    };
+    SwapExecution {
+        amount_in,
+        amount_out,
+        input_token: action.input.clone(),
+        output_token: action.output.clone(),
+        pricing: Pricing::Local,
+        price_impact_pct,
     }
 }
 
+/// Moves the swap and its network fee through the strategy's wallet, then
+/// reports the fill to the strategy.
+/// Fails without touching the wallet if any balance would go negative.
 fn apply_wallet_updates(
     strategy: &mut StrategyData,
     action: &PendingAction,
     execution: &SwapExecution,
-) {
-    match action.action {
-        SwapAction::SolToUsdc { .. } => {
-            strategy.wallet.sol = (strategy.wallet.sol - execution.amount_in).max(0.0);
-            strategy.wallet.usdc += execution.amount_out;
-        }
-        SwapAction::UsdcToSol { .. } => {
-            strategy.wallet.usdc = (strategy.wallet.usdc - execution.amount_in).max(0.0);
-            strategy.wallet.sol += execution.amount_out;
-        }
+    network_fee: Option<(&Token, u64)>,
+) -> Result<Option<f64>> {
+    let base_before = strategy.wallet.balance(&strategy.pair.base);
+    let mut wallet = strategy.wallet.clone();
+    wallet.debit(&execution.input_token, execution.amount_in)?;
+    wallet.credit(&execution.output_token, execution.amount_out)?;
+    if let Some((sol, lamports)) = network_fee {
+        wallet.debit(sol, lamports)?;
     }
-
-    if let Some(StrategyPostUpdate::AlternatingNext(next)) = action.post_update {
-        if let StrategyState::Alternating { next_swap } = &mut strategy.state {
-            *next_swap = next;
-        }
+    strategy.wallet = wallet;
+
+    let amount_in = execution.input_token.to_whole(execution.amount_in);
+    let amount_out = execution.output_token.to_whole(execution.amount_out);
+    let pair = &strategy.pair;
+    if execution.input_token == pair.base && execution.output_token == pair.quote {
+        strategy
+            .performance
+            .record_sell(amount_in, amount_out, base_before);
+    } else if execution.input_token == pair.quote && execution.output_token == pair.base {
+        strategy.performance.record_buy(amount_in);
+    }
+    let realized_profit = strategy.strategy.on_fill(&Fill {
+        action: &action.action,
+        amount_in,
+        amount_out,
+        price: &action.price,
+        order: action.order.as_ref(),
+    });
+    for order in strategy.strategy.take_orders() {
+        strategy.orders.place(order);
     }
+    if execution.output_token == strategy.pair.base && execution.amount_out > 0 {
+        strategy
+            .orders
+            .attach_exits(amount_out, amount_in / amount_out);
+    }
+    Ok(realized_profit)
 }
 
-fn wraps_sol(input: Token, output: Token) -> bool {
-    matches!(input, Token::Sol) || matches!(output, Token::Sol)
+pub fn format_timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S%.3f UTC").to_string()
 }
 
-fn token_details(token: Token) -> (&'static str, u8) {
-    match token {
-        Token::Sol => (SOL_MINT, SOL_DECIMALS),
-        Token::Usdc => (USDC_MINT, USDC_DECIMALS),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokens::TokenRegistry;
+
+    /// Replays SOL/USD prices one second apart through `config` the way a
+    /// backtest does, and returns the final state.
+    fn simulate(config: &str, prices: &[f64]) -> AppData {
+        let config: Config = toml::from_str(config).unwrap();
+        let tokens = TokenRegistry::from_config(&config).unwrap();
+        let mut data = AppData::new(&config, &tokens).unwrap();
+        let feed_id = config.hermes.feed_id("test", "SOL/USD").unwrap();
+        for (second, &value) in prices.iter().enumerate() {
+            let now = DateTime::<Utc>::UNIX_EPOCH + TimeDelta::seconds(second as i64);
+            let price = PriceInfo {
+                value,
+                publish_time: Some(now.timestamp()),
+            };
+            for action in plan_price_update(&mut data, &feed_id, price, now) {
+                fill(&mut data, &config, action, None, now);
+            }
+            for action in plan_schedule(&mut data, now) {
+                fill(&mut data, &config, action, None, now);
+            }
+        }
+        data
     }
-}
 
-fn to_base_units(amount: f64, decimals: u8) -> Option<u64> {
-    if amount <= 0.0 {
-        return None;
+    /// Second and input token of every swap.
+    fn swaps(data: &AppData) -> Vec<(i64, &str)> {
+        data.history
+            .iter()
+            .map(|record| {
+                (
+                    record.timestamp.timestamp(),
+                    record.input_token.symbol.as_str(),
+                )
+            })
+            .collect()
     }
-    let factor = 10f64.powi(decimals as i32);
-    let value = (amount * factor).round();
-    if value < 1.0 {
-        None
-    } else {
-        Some(value as u64)
+
+    #[test]
+    fn grid_level_crossed_while_a_swap_is_in_flight_still_trades() {
+        let data = simulate(
+            r#"
+            [latency]
+            model = "fixed"
+            millis = 3000
+
+            [[strategies]]
+            kind = "grid"
+            base = "SOL"
+            quote = "USDC"
+            initial_balances = { USDC = 100.0 }
+            params = { lower = 100.0, upper = 200.0, levels = 11, quote_per_level = 10.0 }
+            "#,
+            // Crosses 160 at 1s, filled at 4s; crosses 150 at 2s, while the
+            // first buy is still in flight.
+            &[
+                165.0, 155.0, 145.0, 145.0, 145.0, 145.0, 145.0, 145.0, 145.0, 145.0,
+            ],
+        );
+        assert_eq!(swaps(&data), [(4, "USDC"), (8, "USDC")]);
     }
-}
 
-fn from_base_units(amount: u64, decimals: u8) -> f64 {
-    let factor = 10f64.powi(decimals as i32);
-    (amount as f64) / factor
-}
+    #[test]
+    fn delayed_swap_fills_when_due_at_the_latest_price() {
+        let data = simulate(
+            r#"
+            [latency]
+            model = "fixed"
+            millis = 2000
+
+            [[strategies]]
+            kind = "alternating"
+            base = "SOL"
+            quote = "USDC"
+            initial_balances = { SOL = 1.0 }
+            "#,
+            &[100.0, 100.0, 120.0, 120.0],
+        );
+        // Decided at 0s, held while in flight, filled at 2s at 120.
+        assert_eq!(swaps(&data), [(2, "SOL")]);
+        assert_eq!(data.history[0].price, 120.0);
+        // The buy decided on that fill is still in flight.
+        assert_eq!(data.in_flight.len(), 1);
+    }
 
-fn current_timestamp() -> String {
-    let now: DateTime<Utc> = Utc::now();
-    now.format("%Y-%m-%d %H:%M:%S%.3f UTC").to_string()
+    #[test]
+    fn fired_order_takes_the_tick_and_is_reported_to_the_strategy() {
+        let data = simulate(
+            r#"
+            [[strategies]]
+            kind = "alternating"
+            base = "SOL"
+            quote = "USDC"
+            initial_balances = { SOL = 1.0 }
+            orders = [{ side = "sell_base", kind = { type = "limit", price = 100.0 }, amount = 1.0 }]
+            "#,
+            &[150.0, 150.0],
+        );
+        // The limit sells the SOL Alternating would have sold too; told of
+        // that fill, Alternating buys back on the next tick.
+        assert_eq!(swaps(&data), [(0, "SOL"), (1, "USDC")]);
+        assert!(data.history[0].direction.contains("limit @ 100.0000"));
+    }
+
+    #[test]
+    fn stop_loss_fill_closes_the_strategy_position() {
+        let data = simulate(
+            r#"
+            [[strategies]]
+            kind = "bollinger"
+            base = "SOL"
+            quote = "USDC"
+            initial_balances = { USDC = 100.0 }
+            params = { window = 5, band_width = 1.5 }
+            exits = { stop_loss = 0.05 }
+            "#,
+            &[100.0, 101.0, 100.0, 101.0, 90.0, 85.0],
+        );
+        assert_eq!(swaps(&data), [(4, "USDC"), (5, "SOL")]);
+        let state = data.strategies[0].strategy.state();
+        assert_eq!(state["position"]["side"], "flat");
+    }
+
+    #[test]
+    fn network_fee_is_waived_when_sol_dust_cannot_cover_it() {
+        let data = simulate(
+            r#"
+            [[tokens]]
+            symbol = "SOL"
+            mint = "So11111111111111111111111111111111111111112"
+            decimals = 9
+            feed = "SOL/USD"
+
+            [[tokens]]
+            symbol = "USDC"
+            mint = "EPjFWdd5AufqSSqeMqejdX3tqZZzcny9qE8P4AQV7B7"
+            decimals = 6
+
+            [[tokens]]
+            symbol = "JUP"
+            mint = "JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN"
+            decimals = 6
+            feed = "SOL/USD"
+
+            [[strategies]]
+            kind = "alternating"
+            base = "JUP"
+            quote = "USDC"
+            initial_balances = { JUP = 1.0, SOL = 0.000001 }
+            "#,
+            &[1.0, 1.0],
+        );
+        assert_eq!(swaps(&data), [(0, "JUP"), (1, "USDC")]);
+        assert!(
+            data.history
+                .iter()
+                .all(|record| record.fees.network_lamports() == 0)
+        );
+        let strategy = &data.strategies[0];
+        let sol = data.fees.sol().unwrap();
+        assert_eq!(strategy.wallet.units(sol), 1_000);
+    }
 }
@@ -0,0 +1,221 @@
+use crate::config::SizingConfig;
+use crate::indicators::RealizedVol;
+use crate::model::PriceInfo;
+use crate::strategies::{SwapAction, TickContext};
+use chrono::Utc;
+
+/// Resizes the swaps a strategy proposes according to its `sizing` config.
+/// The strategy still decides direction; the sizer decides how much.
+#[derive(Debug)]
+pub struct Sizer {
+    config: SizingConfig,
+    /// Realized volatility of the pair price, for the modes that need it.
+    vol: Option<RealizedVol>,
+}
+
+impl Sizer {
+    pub fn new(config: &SizingConfig) -> Self {
+        let vol = match *config {
+            SizingConfig::VolTarget { vol_window, .. } | SizingConfig::Kelly { vol_window, .. } => {
+                Some(RealizedVol::new(vol_window))
+            }
+            SizingConfig::Fixed { .. } | SizingConfig::PercentOfEquity { .. } => None,
+        };
+        Self {
+            config: config.clone(),
+            vol,
+        }
+    }
+
+    /// Feeds a pair price tick into the volatility estimate.
+    pub fn observe(&mut self, price: &PriceInfo) {
+        if let Some(vol) = &mut self.vol {
+            let timestamp = price.publish_time.unwrap_or_else(|| Utc::now().timestamp());
+            vol.update(timestamp, price.value);
+        }
+    }
+
+    pub fn realized_vol(&self) -> Option<f64> {
+        self.vol.as_ref()?.value()
+    }
+
+    /// Returns `action` with its amount replaced by the configured size,
+    /// capped at the wallet balance. Returns `None` when the size is below
+    /// the input token's minimum or cannot be computed yet.
+    pub fn size(&self, action: SwapAction, ctx: &TickContext) -> Option<SwapAction> {
+        let pair = ctx.pair;
+        let price = ctx.price.value;
+        let selling_base = action.input == pair.base;
+
+        let amount = match self.config {
+            SizingConfig::Fixed {
+                base_amount,
+                quote_amount,
+            } => {
+                if selling_base {
+                    base_amount
+                } else {
+                    quote_amount
+                }
+            }
+            _ => {
+                let base_balance = ctx.wallet.balance(&pair.base);
+                let equity = base_balance * price + ctx.wallet.balance(&pair.quote);
+                let notional = equity * self.equity_fraction()?;
+                if selling_base {
+                    notional / price
+                } else {
+                    notional
+                }
+            }
+        };
+
+        let amount = amount.min(ctx.wallet.balance(&action.input));
+        (amount.is_finite() && amount > action.input.min_trade_amount).then_some(SwapAction {
+            amount_in: amount,
+            ..action
+        })
+    }
+
+    /// Share of equity a single trade may use.
+    fn equity_fraction(&self) -> Option<f64> {
+        match self.config {
+            SizingConfig::Fixed { .. } => None,
+            SizingConfig::PercentOfEquity { fraction } => Some(fraction),
+            SizingConfig::VolTarget {
+                target_vol,
+                max_fraction,
+                ..
+            } => {
+                let vol = self.realized_vol().filter(|vol| *vol > 0.0)?;
+                Some((target_vol / vol).min(max_fraction))
+            }
+            SizingConfig::Kelly {
+                kelly_fraction,
+                expected_return,
+                max_fraction,
+                ..
+            } => {
+                let vol = self.realized_vol().filter(|vol| *vol > 0.0)?;
+                let kelly = expected_return / vol.powi(2);
+                Some((kelly_fraction * kelly).clamp(0.0, max_fraction))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::model::WalletState;
+    use crate::tokens::{TokenRegistry, TradingPair};
+
+    fn setup(sol: f64, usdc: f64) -> (TradingPair, WalletState) {
+        let tokens = TokenRegistry::from_config(&Config::default()).unwrap();
+        let balances = [("SOL".to_string(), sol), ("USDC".to_string(), usdc)]
+            .into_iter()
+            .collect();
+        (
+            tokens.pair("SOL", "USDC").unwrap(),
+            tokens.wallet(&balances).unwrap(),
+        )
+    }
+
+    fn price(second: i64, value: f64) -> PriceInfo {
+        PriceInfo {
+            value,
+            publish_time: Some(second),
+        }
+    }
+
+    /// Sizes a buy and a sell of a nominal amount at `value`.
+    fn sized(
+        sizer: &Sizer,
+        wallet: &WalletState,
+        pair: &TradingPair,
+        value: f64,
+    ) -> [Option<f64>; 2] {
+        let price = price(0, value);
+        let ctx = TickContext {
+            pair,
+            price: &price,
+            wallet,
+        };
+        [
+            SwapAction::buy_base(pair, 1.0),
+            SwapAction::sell_base(pair, 1.0),
+        ]
+        .map(|action| sizer.size(action, &ctx).map(|action| action.amount_in))
+    }
+
+    fn vol_target(target_vol: f64) -> Sizer {
+        Sizer::new(&SizingConfig::VolTarget {
+            target_vol,
+            vol_window: 10,
+            max_fraction: 1.0,
+        })
+    }
+
+    fn oscillate(sizer: &mut Sizer) {
+        for second in 0..20 {
+            sizer.observe(&price(second, if second % 2 == 0 { 100.0 } else { 101.0 }));
+        }
+    }
+
+    #[test]
+    fn fixed_sizes_are_capped_at_the_balance() {
+        let (pair, wallet) = setup(0.5, 100.0);
+        let sizer = Sizer::new(&SizingConfig::Fixed {
+            base_amount: 2.0,
+            quote_amount: 30.0,
+        });
+        assert_eq!(
+            sized(&sizer, &wallet, &pair, 100.0),
+            [Some(30.0), Some(0.5)]
+        );
+    }
+
+    #[test]
+    fn percent_of_equity_trades_a_share_of_both_sides() {
+        let (pair, wallet) = setup(1.0, 100.0);
+        let sizer = Sizer::new(&SizingConfig::PercentOfEquity { fraction: 0.25 });
+        // Equity is 1 SOL at 100 plus 100 USDC.
+        assert_eq!(
+            sized(&sizer, &wallet, &pair, 100.0),
+            [Some(50.0), Some(0.5)]
+        );
+    }
+
+    #[test]
+    fn sizes_below_the_minimum_are_dropped() {
+        let (pair, wallet) = setup(0.0, 0.005);
+        let sizer = Sizer::new(&SizingConfig::PercentOfEquity { fraction: 1.0 });
+        assert_eq!(sized(&sizer, &wallet, &pair, 100.0), [None, None]);
+    }
+
+    #[test]
+    fn vol_target_waits_for_volatility_and_scales_with_the_target() {
+        let (pair, wallet) = setup(0.0, 1000.0);
+        let (mut low, mut high) = (vol_target(0.1), vol_target(0.2));
+        assert_eq!(sized(&low, &wallet, &pair, 100.0)[0], None);
+        oscillate(&mut low);
+        oscillate(&mut high);
+        let low = sized(&low, &wallet, &pair, 100.0)[0].unwrap();
+        let high = sized(&high, &wallet, &pair, 100.0)[0].unwrap();
+        assert!((high / low - 2.0).abs() < 1e-9, "{low} {high}");
+    }
+
+    #[test]
+    fn kelly_with_a_negative_edge_does_not_trade() {
+        let (pair, wallet) = setup(1.0, 100.0);
+        let mut sizer = Sizer::new(&SizingConfig::Kelly {
+            kelly_fraction: 0.5,
+            expected_return: -0.1,
+            vol_window: 10,
+            max_fraction: 1.0,
+        });
+        oscillate(&mut sizer);
+        assert_eq!(sized(&sizer, &wallet, &pair, 100.0), [None, None]);
+    }
+}
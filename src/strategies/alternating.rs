@@ -0,0 +1,80 @@
+use super::{Fill, Strategy, StrategyConfig, SwapAction, TickContext, label_or};
+use crate::orders::OrderSide;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+/// Swaps the whole base balance into quote, then the whole quote balance
+/// back, on every tick.
+#[derive(Debug)]
+pub struct Alternating {
+    label: String,
+    next_swap: SwapDirection,
+    last_fill: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SwapDirection {
+    ToQuote,
+    ToBase,
+}
+
+pub fn build(config: &StrategyConfig) -> Result<Box<dyn Strategy>> {
+    Ok(Box::new(Alternating {
+        label: label_or(config, "Alternating"),
+        next_swap: SwapDirection::ToQuote,
+        last_fill: None,
+    }))
+}
+
+impl Strategy for Alternating {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn on_tick(&mut self, ctx: &TickContext) -> Option<SwapAction> {
+        let pair = ctx.pair;
+        match self.next_swap {
+            SwapDirection::ToQuote => {
+                let balance = ctx.wallet.balance(&pair.base);
+                if balance <= pair.base.min_trade_amount {
+                    return None;
+                }
+                Some(SwapAction::sell_base(pair, balance))
+            }
+            SwapDirection::ToBase => {
+                let balance = ctx.wallet.balance(&pair.quote);
+                if balance <= pair.quote.min_trade_amount {
+                    return None;
+                }
+                Some(SwapAction::buy_base(pair, balance))
+            }
+        }
+    }
+
+    fn on_fill(&mut self, fill: &Fill) -> Option<f64> {
+        self.last_fill = Some(json!({
+            "input": fill.action.input.symbol,
+            "output": fill.action.output.symbol,
+            "amount_in": fill.amount_in,
+            "amount_out": fill.amount_out,
+            "price": fill.price.value,
+        }));
+        self.next_swap = match (fill.order, self.next_swap) {
+            (Some(order), _) if order.order.side == OrderSide::SellBase => SwapDirection::ToBase,
+            (Some(_), _) => SwapDirection::ToQuote,
+            (None, SwapDirection::ToQuote) => SwapDirection::ToBase,
+            (None, SwapDirection::ToBase) => SwapDirection::ToQuote,
+        };
+        None
+    }
+
+    fn parameters(&self) -> Value {
+        json!({})
+    }
+
+    fn state(&self) -> Value {
+        json!({ "next_swap": self.next_swap, "last_fill": self.last_fill })
+    }
+}
@@ -0,0 +1,177 @@
+use super::{Fill, Strategy, StrategyConfig, SwapAction, TickContext, label_or, parse_params};
+use crate::indicators::RollingStats;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Fades moves outside Bollinger bands: buys base below the lower band and
+/// sells it above the upper band, then unwinds the position once price
+/// reverts to the mean.
+#[derive(Debug)]
+pub struct Bollinger {
+    label: String,
+    params: Params,
+    stats: RollingStats,
+    position: Position,
+}
+
+/// Tags of entry swaps; untagged fills, including those of resting orders
+/// such as stop-losses, unwind the open position.
+const ENTER_LONG: u64 = 1;
+const ENTER_SHORT: u64 = 2;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case", tag = "side")]
+enum Position {
+    Flat,
+    /// Bought `base` below the lower band.
+    Long {
+        base: f64,
+    },
+    /// Sold base above the upper band for `quote`.
+    Short {
+        quote: f64,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct Params {
+    /// Number of ticks in the rolling window.
+    window: usize,
+    /// Distance of the bands from the mean, in standard deviations.
+    band_width: f64,
+    /// Distance from the mean, in standard deviations, at which an open
+    /// position is unwound. Zero exits at the mean itself.
+    exit_width: f64,
+    /// Fraction of the quote balance spent when price breaks the lower band.
+    buy_quote_fraction: f64,
+    /// Fraction of the base balance sold when price breaks the upper band.
+    sell_base_fraction: f64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            window: 20,
+            band_width: 2.0,
+            exit_width: 0.0,
+            buy_quote_fraction: 0.5,
+            sell_base_fraction: 0.5,
+        }
+    }
+}
+
+pub fn build(config: &StrategyConfig) -> Result<Box<dyn Strategy>> {
+    let params: Params = parse_params(config)?;
+    if params.window < 2 {
+        bail!("window must be at least 2, got {}", params.window);
+    }
+    if params.band_width <= 0.0 {
+        bail!("band_width must be positive, got {}", params.band_width);
+    }
+    if !(params.exit_width >= 0.0 && params.exit_width < params.band_width) {
+        bail!(
+            "exit_width must be in [0, band_width), got {}",
+            params.exit_width
+        );
+    }
+    for (name, fraction) in [
+        ("buy_quote_fraction", params.buy_quote_fraction),
+        ("sell_base_fraction", params.sell_base_fraction),
+    ] {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            bail!("{name} must be in (0, 1], got {fraction}");
+        }
+    }
+    Ok(Box::new(Bollinger {
+        label: label_or(config, "Bollinger Range"),
+        stats: RollingStats::new(params.window),
+        params,
+        position: Position::Flat,
+    }))
+}
+
+impl Bollinger {
+    fn bands(&self) -> Option<(f64, f64, f64)> {
+        let (mean, std_dev) = self.stats.value()?;
+        let offset = self.params.band_width * std_dev;
+        Some((mean - offset, mean, mean + offset))
+    }
+}
+
+impl Strategy for Bollinger {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn on_tick(&mut self, ctx: &TickContext) -> Option<SwapAction> {
+        let pair = ctx.pair;
+        let price = ctx.price.value;
+        let (mean, std_dev) = self.stats.update(price)?;
+        if std_dev <= 0.0 {
+            return None;
+        }
+        let z_score = (price - mean) / std_dev;
+
+        let base_balance = ctx.wallet.balance(&pair.base);
+        let quote_balance = ctx.wallet.balance(&pair.quote);
+        let min_base = pair.base.min_trade_amount;
+        let min_quote = pair.quote.min_trade_amount;
+
+        match self.position {
+            Position::Flat if z_score <= -self.params.band_width => {
+                let amount = quote_balance * self.params.buy_quote_fraction;
+                (amount > min_quote)
+                    .then(|| SwapAction::buy_base(pair, amount).with_tag(ENTER_LONG))
+            }
+            Position::Flat if z_score >= self.params.band_width => {
+                let amount = base_balance * self.params.sell_base_fraction;
+                (amount > min_base)
+                    .then(|| SwapAction::sell_base(pair, amount).with_tag(ENTER_SHORT))
+            }
+            Position::Long { base } if z_score >= -self.params.exit_width => {
+                let amount = base.min(base_balance);
+                (amount > min_base).then(|| SwapAction::sell_base(pair, amount))
+            }
+            Position::Short { quote } if z_score <= self.params.exit_width => {
+                let amount = quote.min(quote_balance);
+                (amount > min_quote).then(|| SwapAction::buy_base(pair, amount))
+            }
+            _ => None,
+        }
+    }
+
+    fn on_fill(&mut self, fill: &Fill) -> Option<f64> {
+        self.position = match fill.action.tag {
+            Some(ENTER_LONG) => Position::Long {
+                base: fill.amount_out,
+            },
+            Some(ENTER_SHORT) => Position::Short {
+                quote: fill.amount_out,
+            },
+            _ => Position::Flat,
+        };
+        None
+    }
+
+    fn indicators(&self) -> Vec<(String, f64)> {
+        self.bands()
+            .map(|(lower, mean, upper)| {
+                vec![
+                    ("lower".to_string(), lower),
+                    ("mean".to_string(), mean),
+                    ("upper".to_string(), upper),
+                ]
+            })
+            .unwrap_or_default()
+    }
+
+    fn parameters(&self) -> Value {
+        json!(self.params)
+    }
+
+    fn state(&self) -> Value {
+        json!({ "position": self.position })
+    }
+}
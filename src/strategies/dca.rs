@@ -0,0 +1,157 @@
+use super::schedule::{Schedule, Timer};
+use super::{Fill, Strategy, StrategyConfig, SwapAction, TickContext, label_or, parse_params};
+use anyhow::{Result, bail};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Buys base on a wall-clock schedule regardless of price ticks. In value
+/// averaging mode each period tops the position up to a target value that
+/// grows by `quote_amount` per period instead of spending a fixed amount.
+#[derive(Debug)]
+pub struct Dca {
+    label: String,
+    params: Params,
+    timer: Timer,
+    periods: u64,
+    /// Symbol of the configured base token, to tell buys from sells.
+    base: String,
+    /// Net base acquired by this strategy.
+    position_base: f64,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Mode {
+    Fixed,
+    ValueAveraging,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct Params {
+    /// Quote spent per period, or the per-period growth of the target
+    /// position value in value averaging mode.
+    quote_amount: f64,
+    /// Seconds between runs. Defaults to hourly when `cron` is not set.
+    interval_secs: Option<u64>,
+    /// Cron expression evaluated in UTC, with a leading seconds field
+    /// (`0 0 * * * *` runs hourly).
+    cron: Option<String>,
+    mode: Mode,
+    /// Cap on the quote a single value averaging period may spend.
+    max_quote_per_period: Option<f64>,
+    /// Whether value averaging sells base when the position is above target.
+    allow_sell: bool,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            quote_amount: 10.0,
+            interval_secs: None,
+            cron: None,
+            mode: Mode::Fixed,
+            max_quote_per_period: None,
+            allow_sell: false,
+        }
+    }
+}
+
+const DEFAULT_INTERVAL_SECS: i64 = 3600;
+
+pub fn build(config: &StrategyConfig) -> Result<Box<dyn Strategy>> {
+    let params: Params = parse_params(config)?;
+    if params.quote_amount <= 0.0 {
+        bail!("quote_amount must be positive, got {}", params.quote_amount);
+    }
+    if let Some(max) = params.max_quote_per_period
+        && max <= 0.0
+    {
+        bail!("max_quote_per_period must be positive, got {max}");
+    }
+    let schedule = Schedule::from_params(params.interval_secs, params.cron.as_deref())?.unwrap_or(
+        Schedule::Interval(TimeDelta::seconds(DEFAULT_INTERVAL_SECS)),
+    );
+    Ok(Box::new(Dca {
+        label: label_or(config, "DCA"),
+        params,
+        timer: Timer::new(schedule),
+        periods: 0,
+        base: config.base.clone(),
+        position_base: 0.0,
+    }))
+}
+
+impl Dca {
+    /// Quote to spend (positive) or base to sell (negative) this period.
+    fn value_averaging_gap(&self, price: f64) -> f64 {
+        let target = self.periods as f64 * self.params.quote_amount;
+        target - self.position_base * price
+    }
+}
+
+impl Strategy for Dca {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn on_tick(&mut self, _ctx: &TickContext) -> Option<SwapAction> {
+        None
+    }
+
+    fn on_schedule(&mut self, ctx: &TickContext, now: DateTime<Utc>) -> Option<SwapAction> {
+        let pair = ctx.pair;
+        if !self.timer.fire(now) {
+            return None;
+        }
+        self.periods += 1;
+
+        let price = ctx.price.value;
+        let quote_balance = ctx.wallet.balance(&pair.quote);
+        let min_quote = pair.quote.min_trade_amount;
+        match self.params.mode {
+            Mode::Fixed => {
+                let amount = self.params.quote_amount.min(quote_balance);
+                (amount > min_quote).then(|| SwapAction::buy_base(pair, amount))
+            }
+            Mode::ValueAveraging => {
+                let gap = self.value_averaging_gap(price);
+                if gap >= 0.0 {
+                    let cap = self.params.max_quote_per_period.unwrap_or(f64::INFINITY);
+                    let amount = gap.min(cap).min(quote_balance);
+                    (amount > min_quote).then(|| SwapAction::buy_base(pair, amount))
+                } else if self.params.allow_sell {
+                    let amount = (-gap / price)
+                        .min(self.position_base)
+                        .min(ctx.wallet.balance(&pair.base));
+                    (amount > pair.base.min_trade_amount)
+                        .then(|| SwapAction::sell_base(pair, amount))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn on_fill(&mut self, fill: &Fill) -> Option<f64> {
+        if fill.action.output.symbol == self.base {
+            self.position_base += fill.amount_out;
+        } else if fill.action.input.symbol == self.base {
+            self.position_base = (self.position_base - fill.amount_in).max(0.0);
+        }
+        None
+    }
+
+    fn parameters(&self) -> Value {
+        json!(self.params)
+    }
+
+    fn state(&self) -> Value {
+        json!({
+            "next_due": self.timer.next_due().map(|due| due.to_rfc3339()),
+            "periods": self.periods,
+            "position_base": self.position_base,
+        })
+    }
+}
@@ -0,0 +1,356 @@
+use super::{Fill, Strategy, StrategyConfig, SwapAction, TickContext, label_or, parse_params};
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Places a ladder of virtual levels between `lower` and `upper`. Crossing a
+/// level downwards buys base into the grid cell below it; crossing the next
+/// level up sells that base again, completing a round trip.
+#[derive(Debug)]
+pub struct Grid {
+    label: String,
+    params: Params,
+    levels: Vec<f64>,
+    /// One cell per gap between adjacent levels; cell `i` buys at
+    /// `levels[i]` and sells at `levels[i + 1]`.
+    cells: Vec<Cell>,
+    /// Price up to which level crossings have been handled.
+    last_price: Option<f64>,
+    round_trips: u64,
+    total_profit: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case", tag = "status")]
+enum Cell {
+    Empty,
+    Filled { base: f64, cost_quote: f64 },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Side {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Spacing {
+    Arithmetic,
+    Geometric,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct Params {
+    lower: f64,
+    upper: f64,
+    /// Number of levels, including both bounds.
+    levels: usize,
+    spacing: Spacing,
+    /// Quote spent each time a level is bought.
+    quote_per_level: f64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            lower: 120.0,
+            upper: 200.0,
+            levels: 17,
+            spacing: Spacing::Arithmetic,
+            quote_per_level: 20.0,
+        }
+    }
+}
+
+pub fn build(config: &StrategyConfig) -> Result<Box<dyn Strategy>> {
+    let params: Params = parse_params(config)?;
+    if !(params.lower > 0.0 && params.lower < params.upper) {
+        bail!(
+            "expected 0 < lower < upper, got {} and {}",
+            params.lower,
+            params.upper
+        );
+    }
+    if params.levels < 2 {
+        bail!("levels must be at least 2, got {}", params.levels);
+    }
+    if params.quote_per_level <= 0.0 {
+        bail!(
+            "quote_per_level must be positive, got {}",
+            params.quote_per_level
+        );
+    }
+    let levels = ladder(&params);
+    Ok(Box::new(Grid {
+        label: label_or(config, "Grid"),
+        cells: vec![Cell::Empty; levels.len() - 1],
+        levels,
+        params,
+        last_price: None,
+        round_trips: 0,
+        total_profit: 0.0,
+    }))
+}
+
+fn ladder(params: &Params) -> Vec<f64> {
+    let steps = (params.levels - 1) as f64;
+    (0..params.levels)
+        .map(|index| {
+            let position = index as f64 / steps;
+            match params.spacing {
+                Spacing::Arithmetic => params.lower + (params.upper - params.lower) * position,
+                Spacing::Geometric => params.lower * (params.upper / params.lower).powf(position),
+            }
+        })
+        .collect()
+}
+
+impl Grid {
+    /// Handles the first actionable level crossed between `from` and `to`.
+    /// Returns the level price crossings have been handled up to, and the
+    /// order to place there, if any.
+    fn next_crossing(&self, ctx: &TickContext, from: f64, to: f64) -> (f64, Option<SwapAction>) {
+        let pair = ctx.pair;
+        if to < from {
+            // Falling: walk levels downwards, buying into empty cells.
+            for (index, &level) in self.levels.iter().enumerate().rev() {
+                if level >= from || level < to || index == self.cells.len() {
+                    continue;
+                }
+                if let Cell::Empty = self.cells[index] {
+                    let amount = self
+                        .params
+                        .quote_per_level
+                        .min(ctx.wallet.balance(&pair.quote));
+                    if amount > pair.quote.min_trade_amount {
+                        return (level, Some(SwapAction::buy_base(pair, amount)));
+                    }
+                }
+            }
+        } else {
+            // Rising: walk levels upwards, selling cells that close here.
+            for (index, &level) in self.levels.iter().enumerate() {
+                if level <= from || level > to || index == 0 {
+                    continue;
+                }
+                if let Cell::Filled { base, .. } = self.cells[index - 1] {
+                    let amount = base.min(ctx.wallet.balance(&pair.base));
+                    if amount > pair.base.min_trade_amount {
+                        return (level, Some(SwapAction::sell_base(pair, amount)));
+                    }
+                }
+            }
+        }
+        (to, None)
+    }
+
+    fn cell_for(&self, level: f64, side: Side) -> Option<usize> {
+        let index = self.levels.iter().position(|&value| value == level)?;
+        match side {
+            Side::Buy => Some(index),
+            Side::Sell => index.checked_sub(1),
+        }
+    }
+}
+
+/// Packs the cell index and side of a grid swap into its tag.
+fn encode_tag(cell: usize, side: Side) -> u64 {
+    let side = match side {
+        Side::Buy => 0,
+        Side::Sell => 1,
+    };
+    (cell as u64) << 1 | side
+}
+
+fn decode_tag(tag: u64) -> (usize, Side) {
+    let side = if tag & 1 == 0 { Side::Buy } else { Side::Sell };
+    ((tag >> 1) as usize, side)
+}
+
+impl Strategy for Grid {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn on_tick(&mut self, ctx: &TickContext) -> Option<SwapAction> {
+        let price = ctx.price.value;
+        let from = self.last_price.replace(price)?;
+
+        let (handled_to, action) = self.next_crossing(ctx, from, price);
+        // Stop at the level just traded so further crossings in the same
+        // move are picked up on the next tick.
+        self.last_price = Some(handled_to);
+        let action = action?;
+        let side = if action.output == ctx.pair.base {
+            Side::Buy
+        } else {
+            Side::Sell
+        };
+        let cell = self.cell_for(handled_to, side)?;
+        Some(action.with_tag(encode_tag(cell, side)))
+    }
+
+    fn on_fill(&mut self, fill: &Fill) -> Option<f64> {
+        // Resting-order fills are untagged and leave the cells alone.
+        let (index, side) = decode_tag(fill.action.tag?);
+        match (side, *self.cells.get(index)?) {
+            (Side::Buy, _) => {
+                self.cells[index] = Cell::Filled {
+                    base: fill.amount_out,
+                    cost_quote: fill.amount_in,
+                };
+                None
+            }
+            (Side::Sell, Cell::Filled { cost_quote, .. }) => {
+                self.cells[index] = Cell::Empty;
+                let profit = fill.amount_out - cost_quote;
+                self.round_trips += 1;
+                self.total_profit += profit;
+                Some(profit)
+            }
+            (Side::Sell, Cell::Empty) => None,
+        }
+    }
+
+    fn indicators(&self) -> Vec<(String, f64)> {
+        vec![("grid_profit".to_string(), self.total_profit)]
+    }
+
+    fn parameters(&self) -> Value {
+        json!(self.params)
+    }
+
+    fn state(&self) -> Value {
+        let cells = self
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| {
+                json!({
+                    "buy_at": self.levels[index],
+                    "sell_at": self.levels[index + 1],
+                    "cell": cell,
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "cells": cells,
+            "round_trips": self.round_trips,
+            "total_profit": self.total_profit,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::model::{PriceInfo, WalletState};
+    use crate::tokens::{TokenRegistry, TradingPair};
+
+    struct Harness {
+        grid: Box<dyn Strategy>,
+        pair: TradingPair,
+        wallet: WalletState,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let config: Config = toml::from_str(
+                r#"
+                [[strategies]]
+                kind = "grid"
+                base = "SOL"
+                quote = "USDC"
+                params = { lower = 100.0, upper = 200.0, levels = 11, quote_per_level = 15.0 }
+                "#,
+            )
+            .unwrap();
+            let tokens = TokenRegistry::from_config(&config).unwrap();
+            let balances = [("USDC".to_string(), 100.0)].into_iter().collect();
+            Self {
+                grid: build(&config.strategies[0]).unwrap(),
+                pair: tokens.pair("SOL", "USDC").unwrap(),
+                wallet: tokens.wallet(&balances).unwrap(),
+            }
+        }
+
+        /// Ticks at `price` and fills any swap there without costs,
+        /// returning the profit the grid reports.
+        fn tick(&mut self, price: f64) -> Option<f64> {
+            let price = PriceInfo {
+                value: price,
+                publish_time: None,
+            };
+            let ctx = TickContext {
+                pair: &self.pair,
+                price: &price,
+                wallet: &self.wallet,
+            };
+            let action = self.grid.on_tick(&ctx)?;
+            let (amount_in, amount_out) = if action.output == self.pair.base {
+                (action.amount_in, action.amount_in / price.value)
+            } else {
+                (action.amount_in, action.amount_in * price.value)
+            };
+            let units = |amount| action.input.to_base_units(amount);
+            self.wallet.debit(&action.input, units(amount_in)).unwrap();
+            self.wallet
+                .credit(&action.output, action.output.to_base_units(amount_out))
+                .unwrap();
+            self.grid.on_fill(&Fill {
+                action: &action,
+                amount_in,
+                amount_out,
+                price: &price,
+                order: None,
+            })
+        }
+    }
+
+    #[test]
+    fn round_trip_books_the_gap_between_levels() {
+        let mut grid = Harness::new();
+        assert_eq!(grid.tick(155.0), None);
+        // Crosses 150 downwards and buys 15 USDC of SOL there.
+        assert_eq!(grid.tick(150.0), None);
+        assert!((grid.wallet.balance(&grid.pair.base) - 0.1).abs() < 1e-9);
+        // Crosses 160 upwards and sells the cell for 16 USDC.
+        let profit = grid.tick(160.0).unwrap();
+        assert!((profit - 1.0).abs() < 1e-6, "{profit}");
+        assert!((grid.wallet.balance(&grid.pair.quote) - 101.0).abs() < 1e-6);
+        assert_eq!(grid.grid.state()["round_trips"], 1);
+    }
+
+    #[test]
+    fn one_level_is_traded_per_tick() {
+        let mut grid = Harness::new();
+        grid.tick(175.0);
+        // A drop through 170, 160 and 150 buys at 170 first, then the rest
+        // on the following ticks.
+        grid.tick(145.0);
+        assert!((grid.wallet.balance(&grid.pair.quote) - 85.0).abs() < 1e-6);
+        grid.tick(145.0);
+        grid.tick(145.0);
+        assert!((grid.wallet.balance(&grid.pair.quote) - 55.0).abs() < 1e-6);
+        assert_eq!(grid.tick(145.0), None);
+        assert!((grid.wallet.balance(&grid.pair.quote) - 55.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn geometric_ladder_spaces_levels_by_ratio() {
+        let params = Params {
+            lower: 100.0,
+            upper: 400.0,
+            levels: 3,
+            spacing: Spacing::Geometric,
+            ..Params::default()
+        };
+        let levels = ladder(&params);
+        assert_eq!(levels.len(), 3);
+        assert!((levels[1] - 200.0).abs() < 1e-9);
+        assert!((levels[2] - 400.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,162 @@
+use super::{Strategy, StrategyConfig, SwapAction, TickContext, label_or, parse_params};
+use crate::indicators::{Ema, Sma};
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Buys base when the fast moving average crosses above the slow one and
+/// sells it when the fast average crosses back below.
+#[derive(Debug)]
+pub struct MaCrossover {
+    label: String,
+    params: Params,
+    fast: MovingAverage,
+    slow: MovingAverage,
+    ticks: usize,
+    /// Whether fast was above slow on the previous evaluated tick.
+    fast_above: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum AverageKind {
+    Sma,
+    Ema,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct Params {
+    average: AverageKind,
+    fast_window: usize,
+    slow_window: usize,
+    /// Ticks to observe before the first trade; never shorter than the slow
+    /// window.
+    warmup_ticks: usize,
+    /// Fraction of the quote balance spent on a bullish cross.
+    buy_quote_fraction: f64,
+    /// Fraction of the base balance sold on a bearish cross.
+    sell_base_fraction: f64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            average: AverageKind::Ema,
+            fast_window: 12,
+            slow_window: 26,
+            warmup_ticks: 26,
+            buy_quote_fraction: 1.0,
+            sell_base_fraction: 1.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum MovingAverage {
+    Sma(Sma),
+    Ema(Ema),
+}
+
+impl MovingAverage {
+    fn new(kind: AverageKind, window: usize) -> Self {
+        match kind {
+            AverageKind::Sma => MovingAverage::Sma(Sma::new(window)),
+            AverageKind::Ema => MovingAverage::Ema(Ema::new(window)),
+        }
+    }
+
+    fn update(&mut self, value: f64) -> Option<f64> {
+        match self {
+            MovingAverage::Sma(sma) => sma.update(value),
+            MovingAverage::Ema(ema) => ema.update(value),
+        }
+    }
+
+    fn value(&self) -> Option<f64> {
+        match self {
+            MovingAverage::Sma(sma) => sma.value(),
+            MovingAverage::Ema(ema) => ema.value(),
+        }
+    }
+}
+
+pub fn build(config: &StrategyConfig) -> Result<Box<dyn Strategy>> {
+    let params: Params = parse_params(config)?;
+    if params.fast_window == 0 || params.fast_window >= params.slow_window {
+        bail!(
+            "fast_window must be positive and shorter than slow_window, got {} and {}",
+            params.fast_window,
+            params.slow_window
+        );
+    }
+    for (name, fraction) in [
+        ("buy_quote_fraction", params.buy_quote_fraction),
+        ("sell_base_fraction", params.sell_base_fraction),
+    ] {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            bail!("{name} must be in (0, 1], got {fraction}");
+        }
+    }
+    Ok(Box::new(MaCrossover {
+        label: label_or(config, "MA Crossover"),
+        fast: MovingAverage::new(params.average, params.fast_window),
+        slow: MovingAverage::new(params.average, params.slow_window),
+        params,
+        ticks: 0,
+        fast_above: None,
+    }))
+}
+
+impl Strategy for MaCrossover {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn on_tick(&mut self, ctx: &TickContext) -> Option<SwapAction> {
+        let price = ctx.price.value;
+        self.ticks += 1;
+        let fast = self.fast.update(price);
+        let slow = self.slow.update(price);
+        let (fast, slow) = fast.zip(slow)?;
+
+        let fast_above = fast > slow;
+        let previous = self.fast_above.replace(fast_above);
+        if self.ticks < self.params.warmup_ticks.max(self.params.slow_window) {
+            return None;
+        }
+
+        let pair = ctx.pair;
+        match (previous, fast_above) {
+            (Some(false), true) => {
+                let amount = ctx.wallet.balance(&pair.quote) * self.params.buy_quote_fraction;
+                (amount > pair.quote.min_trade_amount).then(|| SwapAction::buy_base(pair, amount))
+            }
+            (Some(true), false) => {
+                let amount = ctx.wallet.balance(&pair.base) * self.params.sell_base_fraction;
+                (amount > pair.base.min_trade_amount).then(|| SwapAction::sell_base(pair, amount))
+            }
+            _ => None,
+        }
+    }
+
+    fn indicators(&self) -> Vec<(String, f64)> {
+        [("fast", self.fast.value()), ("slow", self.slow.value())]
+            .into_iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value?)))
+            .collect()
+    }
+
+    fn parameters(&self) -> Value {
+        json!(self.params)
+    }
+
+    fn state(&self) -> Value {
+        json!({
+            "ticks": self.ticks,
+            "fast": self.fast.value(),
+            "slow": self.slow.value(),
+            "fast_above": self.fast_above,
+        })
+    }
+}
@@ -0,0 +1,199 @@
+mod alternating;
+mod bollinger;
+mod dca;
+mod grid;
+mod ma_crossover;
+mod range_trader;
+mod rebalancer;
+mod rsi_reversion;
+mod schedule;
+mod trend_follow;
+
+use crate::benchmark::Benchmarks;
+use crate::config::{Config, StrategyConfig};
+use crate::metrics::Performance;
+use crate::model::{PriceInfo, StrategyData, WalletState};
+use crate::orders::{Order, OrderBook, RestingOrder};
+use crate::risk::RiskManager;
+use crate::sizing::Sizer;
+use crate::tokens::{Token, TokenRegistry, TradingPair};
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+
+/// A trading strategy driven by the ticks of its pair's base feed and,
+/// optionally, by the wall clock.
+///
+/// Implementations live in their own module under `strategies/` and are made
+/// available to the config through [`StrategyRegistry::register`].
+pub trait Strategy: Debug + Send {
+    fn label(&self) -> &str;
+
+    /// Decides on a swap for the current tick, if any.
+    fn on_tick(&mut self, ctx: &TickContext) -> Option<SwapAction>;
+
+    /// Decides on a swap from the wall clock rather than a price tick. Called
+    /// by the scheduler every `simulation.scheduler_tick_secs`.
+    fn on_schedule(&mut self, _ctx: &TickContext, _now: DateTime<Utc>) -> Option<SwapAction> {
+        None
+    }
+
+    /// Called once a swap has been filled, whether the strategy returned it
+    /// from `on_tick` or `on_schedule` or one of its resting orders fired.
+    /// Returns the realized profit, in output tokens, when the fill
+    /// completes a round trip.
+    fn on_fill(&mut self, _fill: &Fill) -> Option<f64> {
+        None
+    }
+
+    /// Orders to rest in the strategy's book, collected after every
+    /// `on_tick`, `on_schedule` and `on_fill`.
+    fn take_orders(&mut self) -> Vec<Order> {
+        Vec::new()
+    }
+
+    /// Named indicator values recorded alongside each swap the strategy makes.
+    fn indicators(&self) -> Vec<(String, f64)> {
+        Vec::new()
+    }
+
+    /// Tunable parameters, for display and export.
+    fn parameters(&self) -> Value;
+
+    /// Internal state, for display and export.
+    fn state(&self) -> Value;
+}
+
+pub struct TickContext<'a> {
+    pub pair: &'a TradingPair,
+    /// Pair price in quote per base.
+    pub price: &'a PriceInfo,
+    pub wallet: &'a WalletState,
+}
+
+#[derive(Debug, Clone)]
+pub struct SwapAction {
+    pub input: Token,
+    pub output: Token,
+    pub amount_in: f64,
+    /// Opaque value the strategy gets back in the swap's `Fill`, to match
+    /// fills to decisions once swaps no longer fill on the deciding tick.
+    pub tag: Option<u64>,
+}
+
+impl SwapAction {
+    pub fn sell_base(pair: &TradingPair, amount_base: f64) -> Self {
+        Self {
+            input: pair.base.clone(),
+            output: pair.quote.clone(),
+            amount_in: amount_base,
+            tag: None,
+        }
+    }
+
+    pub fn buy_base(pair: &TradingPair, amount_quote: f64) -> Self {
+        Self {
+            input: pair.quote.clone(),
+            output: pair.base.clone(),
+            amount_in: amount_quote,
+            tag: None,
+        }
+    }
+
+    pub fn with_tag(self, tag: u64) -> Self {
+        Self {
+            tag: Some(tag),
+            ..self
+        }
+    }
+}
+
+pub struct Fill<'a> {
+    pub action: &'a SwapAction,
+    pub amount_in: f64,
+    pub amount_out: f64,
+    pub price: &'a PriceInfo,
+    /// Resting order the swap filled, or `None` for a swap the strategy
+    /// returned itself.
+    pub order: Option<&'a RestingOrder>,
+}
+
+type Factory = fn(&StrategyConfig) -> Result<Box<dyn Strategy>>;
+
+pub struct StrategyRegistry {
+    factories: HashMap<&'static str, Factory>,
+}
+
+impl StrategyRegistry {
+    pub fn with_builtins() -> Self {
+        let mut registry = Self {
+            factories: HashMap::new(),
+        };
+        registry.register("alternating", alternating::build);
+        registry.register("trend_follow", trend_follow::build);
+        registry.register("range_trader", range_trader::build);
+        registry.register("ma_crossover", ma_crossover::build);
+        registry.register("rsi_reversion", rsi_reversion::build);
+        registry.register("bollinger", bollinger::build);
+        registry.register("dca", dca::build);
+        registry.register("grid", grid::build);
+        registry.register("rebalancer", rebalancer::build);
+        registry
+    }
+
+    pub fn register(&mut self, kind: &'static str, factory: Factory) {
+        self.factories.insert(kind, factory);
+    }
+
+    pub fn build(&self, config: &StrategyConfig) -> Result<Box<dyn Strategy>> {
+        let factory = self
+            .factories
+            .get(config.kind.as_str())
+            .ok_or_else(|| anyhow!("unknown strategy kind `{}`", config.kind))?;
+        factory(config).with_context(|| format!("failed to build strategy `{}`", config.kind))
+    }
+
+    /// Builds every strategy listed in the config with its pair and wallet.
+    pub fn build_all(&self, config: &Config, tokens: &TokenRegistry) -> Result<Vec<StrategyData>> {
+        let mut labels = HashSet::new();
+        let mut strategies = Vec::with_capacity(config.strategies.len());
+        for entry in &config.strategies {
+            let strategy = self.build(entry)?;
+            if !labels.insert(strategy.label().to_string()) {
+                bail!(
+                    "strategy label `{}` is used more than once",
+                    strategy.label()
+                );
+            }
+            let risk = entry
+                .risk
+                .as_ref()
+                .map(|risk| RiskManager::new(strategy.label(), risk));
+            strategies.push(StrategyData {
+                strategy,
+                pair: tokens.pair(&entry.base, &entry.quote)?,
+                wallet: tokens.wallet(&entry.initial_balances)?,
+                sizer: entry.sizing.as_ref().map(Sizer::new),
+                risk,
+                orders: OrderBook::new(&entry.orders, entry.exits.clone()),
+                performance: Performance::default(),
+                benchmarks: Benchmarks::default(),
+            });
+        }
+        Ok(strategies)
+    }
+}
+
+/// Deserializes the `params` table of a strategy entry.
+pub fn parse_params<T: DeserializeOwned>(config: &StrategyConfig) -> Result<T> {
+    toml::Value::Table(config.params.clone())
+        .try_into()
+        .with_context(|| format!("invalid params for strategy `{}`", config.kind))
+}
+
+pub fn label_or(config: &StrategyConfig, default: &str) -> String {
+    config.label.clone().unwrap_or_else(|| default.to_string())
+}
@@ -0,0 +1,140 @@
+use super::schedule::{Schedule, Timer};
+use super::{Strategy, StrategyConfig, SwapAction, TickContext, label_or, parse_params};
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Holds a constant share of the wallet's value in base, trading back to the
+/// target whenever the weight drifts outside a band or on a schedule.
+#[derive(Debug)]
+pub struct Rebalancer {
+    label: String,
+    params: Params,
+    timer: Option<Timer>,
+    /// Base weight observed on the latest tick or scheduled run.
+    base_weight: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct Params {
+    /// Target share of the pair's combined value held in base.
+    target_base_weight: f64,
+    /// Absolute weight drift that triggers a rebalance on a tick. Unset to
+    /// rebalance on the schedule only.
+    drift_band: Option<f64>,
+    /// Seconds between scheduled rebalances.
+    interval_secs: Option<u64>,
+    /// Cron expression for scheduled rebalances, evaluated in UTC with a
+    /// leading seconds field.
+    cron: Option<String>,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            target_base_weight: 0.5,
+            drift_band: Some(0.05),
+            interval_secs: None,
+            cron: None,
+        }
+    }
+}
+
+pub fn build(config: &StrategyConfig) -> Result<Box<dyn Strategy>> {
+    let params: Params = parse_params(config)?;
+    if !(0.0..=1.0).contains(&params.target_base_weight) {
+        bail!(
+            "target_base_weight must be between 0 and 1, got {}",
+            params.target_base_weight
+        );
+    }
+    if let Some(band) = params.drift_band
+        && !(band > 0.0 && band < 1.0)
+    {
+        bail!("drift_band must be between 0 and 1, got {band}");
+    }
+    let schedule = Schedule::from_params(params.interval_secs, params.cron.as_deref())?;
+    if params.drift_band.is_none() && schedule.is_none() {
+        bail!("set drift_band, a schedule, or both");
+    }
+    Ok(Box::new(Rebalancer {
+        label: label_or(config, "Rebalancer"),
+        params,
+        timer: schedule.map(Timer::new),
+        base_weight: None,
+    }))
+}
+
+impl Rebalancer {
+    /// Swap that moves the wallet back to the target weight. Returns `None`
+    /// when the wallet is empty or the trade would be below the minimum size.
+    fn rebalance(&mut self, ctx: &TickContext, force: bool) -> Option<SwapAction> {
+        let pair = ctx.pair;
+        let price = ctx.price.value;
+        let base_value = ctx.wallet.balance(&pair.base) * price;
+        let total_value = base_value + ctx.wallet.balance(&pair.quote);
+        if total_value <= 0.0 {
+            return None;
+        }
+
+        let weight = base_value / total_value;
+        self.base_weight = Some(weight);
+        let drift = weight - self.params.target_base_weight;
+        let outside_band = self
+            .params
+            .drift_band
+            .is_some_and(|band| drift.abs() > band);
+        if !force && !outside_band {
+            return None;
+        }
+
+        let excess_quote = drift * total_value;
+        if excess_quote > 0.0 {
+            let amount = excess_quote / price;
+            (amount > pair.base.min_trade_amount).then(|| SwapAction::sell_base(pair, amount))
+        } else {
+            let amount = -excess_quote;
+            (amount > pair.quote.min_trade_amount).then(|| SwapAction::buy_base(pair, amount))
+        }
+    }
+}
+
+impl Strategy for Rebalancer {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn on_tick(&mut self, ctx: &TickContext) -> Option<SwapAction> {
+        self.rebalance(ctx, false)
+    }
+
+    fn on_schedule(&mut self, ctx: &TickContext, now: DateTime<Utc>) -> Option<SwapAction> {
+        if !self.timer.as_mut()?.fire(now) {
+            return None;
+        }
+        self.rebalance(ctx, true)
+    }
+
+    fn indicators(&self) -> Vec<(String, f64)> {
+        self.base_weight
+            .map(|weight| vec![("base_weight".to_string(), weight)])
+            .unwrap_or_default()
+    }
+
+    fn parameters(&self) -> Value {
+        json!(self.params)
+    }
+
+    fn state(&self) -> Value {
+        json!({
+            "base_weight": self.base_weight,
+            "next_rebalance": self
+                .timer
+                .as_ref()
+                .and_then(Timer::next_due)
+                .map(|due| due.to_rfc3339()),
+        })
+    }
+}
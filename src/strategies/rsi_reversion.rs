@@ -0,0 +1,144 @@
+use super::{Strategy, StrategyConfig, SwapAction, TickContext, label_or, parse_params};
+use crate::indicators::{BarAggregator, Rsi};
+use anyhow::{Result, bail};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Buys base when RSI drops into the oversold zone and sells it when RSI
+/// climbs into the overbought zone, once per zone entry.
+#[derive(Debug)]
+pub struct RsiReversion {
+    label: String,
+    params: Params,
+    rsi: Rsi,
+    bars: Option<BarAggregator>,
+    zone: Zone,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Zone {
+    Oversold,
+    Neutral,
+    Overbought,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct Params {
+    period: usize,
+    oversold: f64,
+    overbought: f64,
+    /// Length of the bars RSI is computed on; zero feeds every tick directly.
+    bar_seconds: u64,
+    /// Fraction of the quote balance spent when RSI turns oversold.
+    buy_quote_fraction: f64,
+    /// Fraction of the base balance sold when RSI turns overbought.
+    sell_base_fraction: f64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            period: 14,
+            oversold: 30.0,
+            overbought: 70.0,
+            bar_seconds: 0,
+            buy_quote_fraction: 0.5,
+            sell_base_fraction: 0.5,
+        }
+    }
+}
+
+pub fn build(config: &StrategyConfig) -> Result<Box<dyn Strategy>> {
+    let params: Params = parse_params(config)?;
+    if params.period < 2 {
+        bail!("period must be at least 2, got {}", params.period);
+    }
+    if !(0.0 < params.oversold && params.oversold < params.overbought && params.overbought < 100.0)
+    {
+        bail!(
+            "expected 0 < oversold < overbought < 100, got {} and {}",
+            params.oversold,
+            params.overbought
+        );
+    }
+    for (name, fraction) in [
+        ("buy_quote_fraction", params.buy_quote_fraction),
+        ("sell_base_fraction", params.sell_base_fraction),
+    ] {
+        if !(fraction > 0.0 && fraction <= 1.0) {
+            bail!("{name} must be in (0, 1], got {fraction}");
+        }
+    }
+    Ok(Box::new(RsiReversion {
+        label: label_or(config, "RSI Reversion"),
+        rsi: Rsi::new(params.period),
+        bars: (params.bar_seconds > 0).then(|| BarAggregator::new(params.bar_seconds)),
+        params,
+        zone: Zone::Neutral,
+    }))
+}
+
+impl Strategy for RsiReversion {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn on_tick(&mut self, ctx: &TickContext) -> Option<SwapAction> {
+        let price = ctx.price.value;
+        let sample = match &mut self.bars {
+            Some(bars) => {
+                let timestamp = ctx
+                    .price
+                    .publish_time
+                    .unwrap_or_else(|| Utc::now().timestamp());
+                bars.update(timestamp, price)?
+            }
+            None => price,
+        };
+        let rsi = self.rsi.update(sample)?;
+
+        let zone = if rsi <= self.params.oversold {
+            Zone::Oversold
+        } else if rsi >= self.params.overbought {
+            Zone::Overbought
+        } else {
+            Zone::Neutral
+        };
+        let entered = zone != self.zone;
+        self.zone = zone;
+        if !entered {
+            return None;
+        }
+
+        let pair = ctx.pair;
+        match zone {
+            Zone::Oversold => {
+                let amount = ctx.wallet.balance(&pair.quote) * self.params.buy_quote_fraction;
+                (amount > pair.quote.min_trade_amount).then(|| SwapAction::buy_base(pair, amount))
+            }
+            Zone::Overbought => {
+                let amount = ctx.wallet.balance(&pair.base) * self.params.sell_base_fraction;
+                (amount > pair.base.min_trade_amount).then(|| SwapAction::sell_base(pair, amount))
+            }
+            Zone::Neutral => None,
+        }
+    }
+
+    fn indicators(&self) -> Vec<(String, f64)> {
+        self.rsi
+            .value()
+            .map(|rsi| vec![("rsi".to_string(), rsi)])
+            .unwrap_or_default()
+    }
+
+    fn parameters(&self) -> Value {
+        json!(self.params)
+    }
+
+    fn state(&self) -> Value {
+        json!({ "rsi": self.rsi.value(), "zone": self.zone })
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::str::FromStr;
+
+/// Wall-clock schedule for strategies driven by `Strategy::on_schedule`.
+#[derive(Debug)]
+pub enum Schedule {
+    Interval(TimeDelta),
+    Cron(Box<cron::Schedule>),
+}
+
+impl Schedule {
+    /// Builds a schedule from the `interval_secs`/`cron` params pair. Returns
+    /// `None` when neither is set.
+    pub fn from_params(interval_secs: Option<u64>, cron: Option<&str>) -> Result<Option<Self>> {
+        match (interval_secs, cron) {
+            (Some(_), Some(_)) => bail!("set either interval_secs or cron, not both"),
+            (Some(0), None) => bail!("interval_secs must be greater than zero"),
+            (Some(secs), None) => {
+                let interval = i64::try_from(secs)
+                    .ok()
+                    .and_then(TimeDelta::try_seconds)
+                    .with_context(|| format!("interval_secs {secs} is too large"))?;
+                Ok(Some(Schedule::Interval(interval)))
+            }
+            (None, Some(expression)) => {
+                let schedule = cron::Schedule::from_str(expression)
+                    .with_context(|| format!("invalid cron expression `{expression}`"))?;
+                Ok(Some(Schedule::Cron(Box::new(schedule))))
+            }
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// First run: immediately for intervals, the next matching time for cron.
+    fn first(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Interval(_) => Some(now),
+            Schedule::Cron(schedule) => schedule.after(&now).next(),
+        }
+    }
+
+    fn next_after(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Interval(interval) => now.checked_add_signed(*interval),
+            Schedule::Cron(schedule) => schedule.after(&now).next(),
+        }
+    }
+}
+
+/// Tracks when a [`Schedule`] is next due. Runs missed while the scheduler
+/// was busy collapse into one.
+#[derive(Debug)]
+pub struct Timer {
+    schedule: Schedule,
+    started: bool,
+    /// `None` once the schedule has no run left, or before it has started.
+    next_due: Option<DateTime<Utc>>,
+}
+
+impl Timer {
+    pub fn new(schedule: Schedule) -> Self {
+        Self {
+            schedule,
+            started: false,
+            next_due: None,
+        }
+    }
+
+    /// Returns whether a run is due at `now`, advancing to the next run if so.
+    pub fn fire(&mut self, now: DateTime<Utc>) -> bool {
+        if !self.started {
+            self.started = true;
+            self.next_due = self.schedule.first(now);
+        }
+        if self.next_due.is_none_or(|due| now < due) {
+            return false;
+        }
+        self.next_due = self.schedule.next_after(now);
+        true
+    }
+
+    pub fn next_due(&self) -> Option<DateTime<Utc>> {
+        self.next_due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_intervals_are_config_errors() {
+        for secs in [u64::MAX, i64::MAX as u64] {
+            let error = Schedule::from_params(Some(secs), None).unwrap_err();
+            assert!(error.to_string().contains("too large"));
+        }
+    }
+
+    #[test]
+    fn interval_past_the_calendar_stops_firing() {
+        let secs = (DateTime::<Utc>::MAX_UTC - DateTime::<Utc>::UNIX_EPOCH).num_seconds() as u64;
+        let schedule = Schedule::from_params(Some(secs), None).unwrap().unwrap();
+        let mut timer = Timer::new(schedule);
+        let now = DateTime::<Utc>::UNIX_EPOCH + TimeDelta::days(1);
+        assert!(timer.fire(now));
+        assert_eq!(timer.next_due(), None);
+        assert!(!timer.fire(now + TimeDelta::days(1)));
+    }
+}
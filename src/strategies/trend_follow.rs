@@ -0,0 +1,90 @@
+use super::{Strategy, StrategyConfig, SwapAction, TickContext, label_or, parse_params};
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Sells base into strength and buys it back on weakness, comparing each tick
+/// with the previous one.
+#[derive(Debug)]
+pub struct TrendFollow {
+    label: String,
+    params: Params,
+    last_price: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct Params {
+    /// Relative move from the previous tick that triggers a trade.
+    threshold: f64,
+    sell_base_amount: f64,
+    buy_quote_amount: f64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            threshold: 0.002,
+            sell_base_amount: 0.15,
+            buy_quote_amount: 25.0,
+        }
+    }
+}
+
+pub fn build(config: &StrategyConfig) -> Result<Box<dyn Strategy>> {
+    let params: Params = parse_params(config)?;
+    if !(params.threshold > 0.0 && params.threshold < 1.0) {
+        bail!(
+            "threshold must be between 0 and 1, got {}",
+            params.threshold
+        );
+    }
+    if params.sell_base_amount <= 0.0 || params.buy_quote_amount <= 0.0 {
+        bail!("trade amounts must be positive");
+    }
+    Ok(Box::new(TrendFollow {
+        label: label_or(config, "Trend Follow"),
+        params,
+        last_price: None,
+    }))
+}
+
+impl Strategy for TrendFollow {
+    fn label(&self) -> &str {
+        &self.label
+    }
+
+    fn on_tick(&mut self, ctx: &TickContext) -> Option<SwapAction> {
+        let pair = ctx.pair;
+        let price = ctx.price.value;
+        let prev = self.last_price.replace(price)?;
+
+        let base_balance = ctx.wallet.balance(&pair.base);
+        let quote_balance = ctx.wallet.balance(&pair.quote);
+        let min_base = pair.base.min_trade_amount;
+        let min_quote = pair.quote.min_trade_amount;
+
+        let change = (price - prev) / prev;
+        if change >= self.params.threshold && base_balance > min_base {
+            let amount = base_balance.min(self.params.sell_base_amount).max(min_base);
+            return Some(SwapAction::sell_base(pair, amount));
+        }
+
+        if change <= -self.params.threshold && quote_balance > min_quote {
+            let amount = quote_balance
+                .min(self.params.buy_quote_amount)
+                .max(min_quote);
+            return Some(SwapAction::buy_base(pair, amount));
+        }
+
+        None
+    }
+
+    fn parameters(&self) -> Value {
+        json!(self.params)
+    }
+
+    fn state(&self) -> Value {
+        json!({ "last_price": self.last_price })
+    }
+}
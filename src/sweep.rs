@@ -0,0 +1,519 @@
+use crate::backtest::{self, PricePoint};
+use crate::config::{Config, StrategyConfig, SweepArgs};
+use crate::metrics::Metrics;
+use anyhow::{Context, Result, bail};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Grid searches beyond this many parameter sets must use random search.
+const MAX_GRID_SIZE: usize = 100_000;
+
+/// Parameter search over one configured strategy, read from a TOML file:
+///
+/// ```toml
+/// strategy = "Trend Follow"
+/// objective = "sharpe"
+/// search = { mode = "random", samples = 200, seed = 7 }
+///
+/// [params]
+/// threshold = { min = 0.001, max = 0.01, steps = 10 }
+/// sell_base_amount = [0.1, 0.15, 0.25]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SweepSpec {
+    /// Label, or kind, of the strategy to tune in the main config.
+    pub strategy: String,
+    #[serde(default)]
+    pub objective: Objective,
+    #[serde(default)]
+    pub search: Search,
+    /// Space searched per parameter; other parameters keep their configured
+    /// values.
+    pub params: BTreeMap<String, ParamSpace>,
+}
+
+/// What parameter sets are ranked by, best first.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    #[default]
+    Sharpe,
+    Sortino,
+    Return,
+    /// Smallest maximum drawdown.
+    Drawdown,
+}
+
+impl Objective {
+    /// Score where higher is better, or `None` if the run has no value for
+    /// the objective.
+    pub fn score(self, metrics: &Metrics) -> Option<f64> {
+        match self {
+            Objective::Sharpe => metrics.sharpe,
+            Objective::Sortino => metrics.sortino,
+            Objective::Return => Some(metrics.total_return),
+            Objective::Drawdown => Some(-metrics.max_drawdown),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Search {
+    /// Every combination of the parameter values.
+    #[default]
+    Grid,
+    /// Independent uniform draws from each parameter's space.
+    Random {
+        samples: usize,
+        #[serde(default)]
+        seed: Option<u64>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ParamSpace {
+    Values(Vec<toml::Value>),
+    /// `steps` evenly spaced values from `min` to `max` in a grid search, or
+    /// uniform draws between them in a random one. Integer bounds yield
+    /// integer values.
+    Range {
+        min: Bound,
+        max: Bound,
+        #[serde(default = "default_steps")]
+        steps: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(untagged)]
+pub enum Bound {
+    Integer(i64),
+    Float(f64),
+}
+
+impl Bound {
+    fn value(self) -> f64 {
+        match self {
+            Bound::Integer(value) => value as f64,
+            Bound::Float(value) => value,
+        }
+    }
+}
+
+fn default_steps() -> usize {
+    5
+}
+
+impl ParamSpace {
+    fn validate(&self, name: &str) -> Result<()> {
+        match self {
+            ParamSpace::Values(values) if values.is_empty() => {
+                bail!("params.{name} must list at least one value")
+            }
+            ParamSpace::Range { min, max, steps } => {
+                if !(min.value().is_finite() && max.value().is_finite()) {
+                    bail!("params.{name} bounds must be finite");
+                }
+                if min.value() > max.value() {
+                    bail!("params.{name}.min must not exceed params.{name}.max");
+                }
+                if *steps == 0 {
+                    bail!("params.{name}.steps must be at least 1");
+                }
+                Ok(())
+            }
+            ParamSpace::Values(_) => Ok(()),
+        }
+    }
+
+    /// Number of values `grid` yields, worked out without building them.
+    fn grid_len(&self) -> usize {
+        match self {
+            ParamSpace::Values(values) => values.len(),
+            // Rounded steps closer than one apart cover every integer in
+            // between; further apart they are all distinct.
+            ParamSpace::Range {
+                min: Bound::Integer(min),
+                max: Bound::Integer(max),
+                steps,
+            } => usize::try_from(max.abs_diff(*min))
+                .ok()
+                .and_then(|span| span.checked_add(1))
+                .map_or(*steps, |values| values.min(*steps)),
+            ParamSpace::Range { min, max, steps } => {
+                if min.value() == max.value() {
+                    1
+                } else {
+                    *steps
+                }
+            }
+        }
+    }
+
+    fn grid(&self) -> Vec<toml::Value> {
+        match self {
+            ParamSpace::Values(values) => values.clone(),
+            ParamSpace::Range { min, max, steps } => {
+                let mut values = Vec::<toml::Value>::new();
+                for step in 0..*steps {
+                    let position = if *steps > 1 {
+                        step as f64 / (*steps - 1) as f64
+                    } else {
+                        0.0
+                    };
+                    let value = interpolate(*min, *max, position);
+                    if values.last() != Some(&value) {
+                        values.push(value);
+                    }
+                }
+                values
+            }
+        }
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> toml::Value {
+        match self {
+            ParamSpace::Values(values) => values[rng.gen_range(0..values.len())].clone(),
+            ParamSpace::Range {
+                min: Bound::Integer(min),
+                max: Bound::Integer(max),
+                ..
+            } => toml::Value::Integer(rng.gen_range(*min..=*max)),
+            ParamSpace::Range { min, max, .. } => {
+                toml::Value::Float(min.value() + (max.value() - min.value()) * rng.r#gen::<f64>())
+            }
+        }
+    }
+}
+
+fn interpolate(min: Bound, max: Bound, position: f64) -> toml::Value {
+    let value = min.value() + (max.value() - min.value()) * position;
+    match (min, max) {
+        (Bound::Integer(_), Bound::Integer(_)) => toml::Value::Integer(value.round() as i64),
+        _ => toml::Value::Float(value),
+    }
+}
+
+impl SweepSpec {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read sweep spec {}", path.display()))?;
+        let spec: Self = toml::from_str(&contents)
+            .with_context(|| format!("failed to parse sweep spec {}", path.display()))?;
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.params.is_empty() {
+            bail!("params must list at least one parameter");
+        }
+        for (name, space) in &self.params {
+            space.validate(name)?;
+        }
+        match self.search {
+            Search::Random { samples: 0, .. } => bail!("search.samples must be at least 1"),
+            Search::Grid => {
+                let size = self
+                    .params
+                    .values()
+                    .map(ParamSpace::grid_len)
+                    .try_fold(1usize, |size, values| size.checked_mul(values));
+                if size.is_none_or(|size| size > MAX_GRID_SIZE) {
+                    bail!(
+                        "grid search covers more than {MAX_GRID_SIZE} parameter sets; use random search"
+                    );
+                }
+            }
+            Search::Random { .. } => {}
+        }
+        Ok(())
+    }
+
+    /// The configured strategy this spec tunes.
+    pub fn target<'a>(&self, config: &'a Config) -> Result<&'a StrategyConfig> {
+        let mut matches = config.strategies.iter().filter(|strategy| {
+            strategy.label.as_deref() == Some(self.strategy.as_str())
+                || strategy.kind == self.strategy
+        });
+        match (matches.next(), matches.next()) {
+            (Some(strategy), None) => Ok(strategy),
+            (None, _) => bail!("no configured strategy is labelled `{}`", self.strategy),
+            (Some(_), Some(_)) => bail!(
+                "more than one configured strategy matches `{}`; refer to it by label",
+                self.strategy
+            ),
+        }
+    }
+
+    /// Parameter sets to try, with only the swept parameters set.
+    pub fn candidates(&self) -> Vec<toml::Table> {
+        match self.search {
+            Search::Grid => {
+                self.params
+                    .iter()
+                    .fold(vec![toml::Table::new()], |sets, (name, space)| {
+                        sets.iter()
+                            .flat_map(|set| {
+                                space.grid().into_iter().map(move |value| {
+                                    let mut set = set.clone();
+                                    set.insert(name.clone(), value);
+                                    set
+                                })
+                            })
+                            .collect()
+                    })
+            }
+            Search::Random { samples, seed } => {
+                let mut rng = match seed {
+                    Some(seed) => StdRng::seed_from_u64(seed),
+                    None => StdRng::from_entropy(),
+                };
+                (0..samples)
+                    .map(|_| {
+                        self.params
+                            .iter()
+                            .map(|(name, space)| (name.clone(), space.sample(&mut rng)))
+                            .collect()
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Backtest of one parameter set.
+#[derive(Debug, Clone, Serialize)]
+pub struct Trial {
+    pub params: toml::Table,
+    pub score: Option<f64>,
+    pub metrics: Option<Metrics>,
+    /// Why the strategy could not be built with these parameters.
+    pub error: Option<String>,
+}
+
+pub fn run_cli(config: &Config, args: &SweepArgs) -> Result<()> {
+    let spec = SweepSpec::from_file(&args.spec)?;
+    let prices = backtest::load_prices(config, &args.history)?;
+    let trials = run(config, &spec, &prices)?;
+
+    let failed = trials.iter().filter(|trial| trial.error.is_some()).count();
+    if let Some(error) = trials.iter().find_map(|trial| trial.error.as_deref()) {
+        eprintln!(
+            "{failed} of {} parameter sets failed, e.g. {error}",
+            trials.len()
+        );
+    }
+    println!(
+        "{:>4}  {:>10}  {:>9}  {:>8}  {:>8}  {:>6}  params",
+        "rank", "score", "return", "sharpe", "max dd", "trips"
+    );
+    for (rank, trial) in trials
+        .iter()
+        .filter(|trial| trial.error.is_none())
+        .take(args.top)
+        .enumerate()
+    {
+        let optional = |value: Option<f64>, scale: f64| {
+            value
+                .map(|value| format!("{:.3}", value * scale))
+                .unwrap_or_else(|| "--".to_string())
+        };
+        let metrics = trial.metrics.as_ref();
+        println!(
+            "{:>4}  {:>10}  {:>8}%  {:>8}  {:>7}%  {:>6}  {}",
+            rank + 1,
+            optional(trial.score, 1.0),
+            optional(metrics.map(|metrics| metrics.total_return), 100.0),
+            optional(metrics.and_then(|metrics| metrics.sharpe), 1.0),
+            optional(metrics.map(|metrics| metrics.max_drawdown), 100.0),
+            metrics.map_or(0, |metrics| metrics.round_trips),
+            describe(&trial.params)
+        );
+    }
+
+    match &args.output {
+        Some(path) => backtest::write_json(Some(path), &trials),
+        None => Ok(()),
+    }
+}
+
+/// Backtests every candidate parameter set across all cores and returns
+/// the trials ranked by the spec's objective, best first.
+pub fn run(config: &Config, spec: &SweepSpec, prices: &[PricePoint]) -> Result<Vec<Trial>> {
+    let strategy = spec.target(config)?;
+    let mut trials = spec
+        .candidates()
+        .into_par_iter()
+        .map(|params| evaluate(config, strategy, params, spec.objective, prices))
+        .collect::<Vec<_>>();
+    trials.sort_by(|a, b| {
+        let score = |trial: &Trial| trial.score.unwrap_or(f64::NEG_INFINITY);
+        score(b).total_cmp(&score(a))
+    });
+    Ok(trials)
+}
+
+/// Backtests `strategy` alone with `params` layered over its configured ones.
+pub fn evaluate(
+    config: &Config,
+    strategy: &StrategyConfig,
+    params: toml::Table,
+    objective: Objective,
+    prices: &[PricePoint],
+) -> Trial {
+    match backtest::run(&candidate_config(config, strategy, &params), prices) {
+        Ok(report) => {
+            let metrics = report
+                .strategies
+                .into_iter()
+                .next()
+                .and_then(|strategy| strategy.metrics);
+            Trial {
+                params,
+                score: metrics
+                    .as_ref()
+                    .and_then(|metrics| objective.score(metrics)),
+                metrics,
+                error: None,
+            }
+        }
+        Err(err) => Trial {
+            params,
+            score: None,
+            metrics: None,
+            error: Some(format!("{err:#}")),
+        },
+    }
+}
+
+/// `config` with `strategy` as its only strategy, `params` layered over its
+/// configured ones.
+pub fn candidate_config(
+    config: &Config,
+    strategy: &StrategyConfig,
+    params: &toml::Table,
+) -> Config {
+    let mut strategy = strategy.clone();
+    strategy.params.extend(params.clone());
+    let mut candidate = config.clone();
+    candidate.strategies = vec![strategy];
+    candidate
+}
+
+/// Renders a parameter set as `name=value` pairs.
+pub fn describe(params: &toml::Table) -> String {
+    params
+        .iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(contents: &str) -> SweepSpec {
+        let spec: SweepSpec = toml::from_str(contents).unwrap();
+        spec.validate().unwrap();
+        spec
+    }
+
+    #[test]
+    fn grid_covers_every_combination() {
+        let spec = spec(
+            r#"
+            strategy = "Trend Follow"
+
+            [params]
+            threshold = { min = 0.001, max = 0.005, steps = 5 }
+            sell_base_amount = [0.1, 0.15, 0.25]
+            window = { min = 2, max = 4, steps = 10 }
+            "#,
+        );
+        let candidates = spec.candidates();
+        // Integer ranges drop the steps that round to the same value.
+        assert_eq!(candidates.len(), 5 * 3 * 3);
+        assert!(candidates.iter().all(|set| set.len() == 3));
+        let windows = candidates
+            .iter()
+            .map(|set| set["window"].as_integer().unwrap())
+            .collect::<std::collections::BTreeSet<_>>();
+        assert_eq!(windows.into_iter().collect::<Vec<_>>(), [2, 3, 4]);
+    }
+
+    #[test]
+    fn random_search_draws_seeded_samples_within_bounds() {
+        let contents = r#"
+            strategy = "Trend Follow"
+            search = { mode = "random", samples = 40, seed = 7 }
+
+            [params]
+            threshold = { min = 0.001, max = 0.01 }
+            window = { min = 2, max = 4 }
+            "#;
+        let candidates = spec(contents).candidates();
+        assert_eq!(candidates.len(), 40);
+        for set in &candidates {
+            let threshold = set["threshold"].as_float().unwrap();
+            assert!((0.001..=0.01).contains(&threshold));
+            assert!((2..=4).contains(&set["window"].as_integer().unwrap()));
+        }
+        assert_eq!(candidates, spec(contents).candidates());
+    }
+
+    #[test]
+    fn grid_len_matches_the_values_built() {
+        let spaces = [
+            "{ min = 0.001, max = 0.005, steps = 5 }",
+            "{ min = 0.5, max = 0.5, steps = 5 }",
+            "{ min = 2, max = 4, steps = 10 }",
+            "{ min = 0, max = 100, steps = 7 }",
+            "{ min = -3, max = 3 }",
+            "[1, 2, 3]",
+        ];
+        for space in spaces {
+            let table: toml::Table = toml::from_str(&format!("space = {space}")).unwrap();
+            let space: ParamSpace = table["space"].clone().try_into().unwrap();
+            assert_eq!(space.grid_len(), space.grid().len(), "{space:?}");
+        }
+    }
+
+    #[test]
+    fn huge_steps_are_rejected_before_building_values() {
+        let spec: SweepSpec = toml::from_str(
+            r#"
+            strategy = "Trend Follow"
+
+            [params]
+            threshold = { min = 0.0, max = 1.0, steps = 1_000_000_000 }
+            "#,
+        )
+        .unwrap();
+        assert!(spec.validate().is_err());
+    }
+
+    #[test]
+    fn oversized_grid_is_rejected() {
+        let spec: SweepSpec = toml::from_str(
+            r#"
+            strategy = "Trend Follow"
+
+            [params]
+            a = { min = 0.0, max = 1.0, steps = 1000 }
+            b = { min = 0.0, max = 1.0, steps = 1000 }
+            "#,
+        )
+        .unwrap();
+        assert!(spec.validate().is_err());
+    }
+}
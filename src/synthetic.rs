@@ -0,0 +1,277 @@
+use crate::config::{Config, CrashConfig, PriceProcess};
+use crate::indicators::SECONDS_PER_YEAR;
+use crate::jupiter::JupiterClient;
+use crate::model::{AppState, PriceInfo};
+use crate::simulation::apply_price_update;
+use anyhow::Result;
+use chrono::Utc;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Poisson, StandardNormal};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Price paths of the feeds in `synthetic.feeds`, advanced one tick at a
+/// time. Paths depend only on the configuration and seed.
+#[derive(Debug)]
+pub struct SyntheticMarket {
+    rng: StdRng,
+    tick_secs: f64,
+    elapsed_secs: f64,
+    feeds: Vec<SyntheticFeed>,
+}
+
+#[derive(Debug)]
+struct SyntheticFeed {
+    feed_id: String,
+    /// Log price of the process, before crashes.
+    log_price: f64,
+    process: PriceProcess,
+    crashes: Vec<CrashConfig>,
+}
+
+impl SyntheticMarket {
+    pub fn new(config: &Config) -> Result<Self> {
+        let synthetic = &config.synthetic;
+        let feeds = synthetic
+            .feeds
+            .iter()
+            .map(|(name, feed)| {
+                Ok(SyntheticFeed {
+                    feed_id: config.hermes.feed_id("synthetic.feeds", name)?,
+                    log_price: feed.initial_price.ln(),
+                    process: feed.process.clone(),
+                    crashes: feed.crashes.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            rng: match synthetic.seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            tick_secs: synthetic.tick_millis as f64 / 1000.0,
+            elapsed_secs: 0.0,
+            feeds,
+        })
+    }
+
+    pub fn tick_interval(&self) -> Duration {
+        Duration::from_secs_f64(self.tick_secs)
+    }
+
+    /// Current price of every feed, keyed by feed id, then advances the
+    /// market by one tick.
+    pub fn next_prices(&mut self) -> Vec<(String, f64)> {
+        let prices = self
+            .feeds
+            .iter()
+            .map(|feed| {
+                let crash_level = feed
+                    .crashes
+                    .iter()
+                    .map(|crash| crash_level(crash, self.elapsed_secs))
+                    .product::<f64>();
+                (feed.feed_id.clone(), feed.log_price.exp() * crash_level)
+            })
+            .collect();
+
+        let years = self.tick_secs / SECONDS_PER_YEAR;
+        for feed in &mut self.feeds {
+            feed.log_price += log_return(&feed.process, feed.log_price, years, &mut self.rng);
+        }
+        self.elapsed_secs += self.tick_secs;
+        prices
+    }
+}
+
+/// Change of the log price over `years`, from an Euler step of the process.
+fn log_return(process: &PriceProcess, log_price: f64, years: f64, rng: &mut StdRng) -> f64 {
+    let mut shock = || rng.sample::<f64, _>(StandardNormal) * years.sqrt();
+    match *process {
+        PriceProcess::Constant => 0.0,
+        PriceProcess::Gbm { drift, volatility } => {
+            (drift - volatility * volatility / 2.0) * years + volatility * shock()
+        }
+        PriceProcess::JumpDiffusion {
+            drift,
+            volatility,
+            jumps_per_year,
+            jump_mean,
+            jump_volatility,
+        } => {
+            // Jumps multiply the price by e^J - 1 on average; taking that out of
+            // the drift keeps `drift` the expected return.
+            let compensator = jumps_per_year
+                * ((jump_mean + jump_volatility * jump_volatility / 2.0).exp() - 1.0);
+            let diffusion = (drift - compensator - volatility * volatility / 2.0) * years
+                + volatility * shock();
+            let jumps = Poisson::new(jumps_per_year * years)
+                .map(|distribution| distribution.sample(rng) as u64)
+                .unwrap_or(0);
+            let jump_sizes = (0..jumps)
+                .map(|_| jump_mean + jump_volatility * rng.sample::<f64, _>(StandardNormal))
+                .sum::<f64>();
+            diffusion + jump_sizes
+        }
+        PriceProcess::OrnsteinUhlenbeck {
+            mean,
+            reversion,
+            volatility,
+        } => reversion * (mean.ln() - log_price) * years + volatility * shock(),
+    }
+}
+
+/// Multiplier a crash applies to the price `elapsed_secs` after startup.
+fn crash_level(crash: &CrashConfig, elapsed_secs: f64) -> f64 {
+    let progress = |since: f64, span: u64| {
+        if since < 0.0 {
+            0.0
+        } else if span == 0 {
+            1.0
+        } else {
+            (since / span as f64).min(1.0)
+        }
+    };
+    let since_start = elapsed_secs - crash.at_secs as f64;
+    let fallen = progress(since_start, crash.fall_secs);
+    let recovered = progress(since_start - crash.fall_secs as f64, crash.recovery_secs);
+    1.0 - crash.drop * fallen * (1.0 - crash.recovery * recovered)
+}
+
+/// Feeds `market` into the simulation in place of the Hermes stream.
+pub async fn run(
+    mut market: SyntheticMarket,
+    state: AppState,
+    config: Arc<Config>,
+    jupiter: Option<Arc<JupiterClient>>,
+) {
+    let mut interval = tokio::time::interval(market.tick_interval());
+    loop {
+        interval.tick().await;
+        let publish_time = Some(Utc::now().timestamp());
+        for (feed_id, value) in market.next_prices() {
+            apply_price_update(
+                &state,
+                &config,
+                &feed_id,
+                PriceInfo {
+                    value,
+                    publish_time,
+                },
+                jupiter.clone(),
+            )
+            .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn market(synthetic: &str) -> SyntheticMarket {
+        let config: Config = toml::from_str(&format!(
+            r#"
+            [[hermes.feeds]]
+            name = "SOL/USD"
+            id = "0x01"
+
+            [[hermes.feeds]]
+            name = "JUP/USD"
+            id = "0x02"
+
+            {synthetic}
+            "#
+        ))
+        .unwrap();
+        SyntheticMarket::new(&config).unwrap()
+    }
+
+    fn path(market: &mut SyntheticMarket, ticks: usize) -> Vec<Vec<(String, f64)>> {
+        (0..ticks).map(|_| market.next_prices()).collect()
+    }
+
+    const TWO_FEEDS: &str = r#"
+        [synthetic.feeds."SOL/USD"]
+        initial_price = 150.0
+        process = { model = "jump_diffusion", volatility = 0.8, jumps_per_year = 5000.0, jump_mean = -0.001, jump_volatility = 0.004 }
+
+        [synthetic.feeds."JUP/USD"]
+        initial_price = 0.5
+        process = { model = "gbm", volatility = 1.2 }
+    "#;
+
+    #[test]
+    fn seeded_markets_repeat_their_paths() {
+        let seeded = |seed: u64| market(&format!("[synthetic]\nseed = {seed}\n{TWO_FEEDS}"));
+        let first = path(&mut seeded(7), 200);
+        assert_eq!(first, path(&mut seeded(7), 200));
+        assert_ne!(first, path(&mut seeded(8), 200));
+        // Feeds run in name order, each opening at its initial price.
+        let ids = first[0]
+            .iter()
+            .map(|(id, _)| id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["02", "01"]);
+        assert!((first[0][0].1 - 0.5).abs() < 1e-9);
+        assert!((first[0][1].1 - 150.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn crash_falls_and_recovers_on_schedule() {
+        let mut market = market(
+            r#"
+            [synthetic.feeds."SOL/USD"]
+            initial_price = 100.0
+            process = { model = "constant" }
+            crashes = [{ at_secs = 10, drop = 0.5, fall_secs = 10, recovery = 0.5, recovery_secs = 10 }]
+            "#,
+        );
+        let prices = path(&mut market, 41)
+            .into_iter()
+            .map(|tick| tick[0].1)
+            .collect::<Vec<_>>();
+        for (second, expected) in [
+            (0, 100.0),
+            (10, 100.0),
+            (15, 75.0),
+            (20, 50.0),
+            (25, 62.5),
+            (30, 75.0),
+            (40, 75.0),
+        ] {
+            assert!(
+                (prices[second] - expected).abs() < 1e-9,
+                "at {second}s expected {expected}, got {}",
+                prices[second]
+            );
+        }
+    }
+
+    #[test]
+    fn jumps_do_not_change_the_expected_return() {
+        // One tick of a year with no diffusion and no drift: the mean price
+        // stays at the initial one only if the jumps are compensated.
+        let samples = 4_000;
+        let mean = (0..samples)
+            .map(|seed| {
+                let mut market = market(&format!(
+                    r#"
+                    [synthetic]
+                    seed = {seed}
+                    tick_millis = 31557600000
+
+                    [synthetic.feeds."SOL/USD"]
+                    initial_price = 1.0
+                    process = {{ model = "jump_diffusion", volatility = 0.0, jumps_per_year = 1.0, jump_mean = -0.2, jump_volatility = 0.1 }}
+                    "#
+                ));
+                market.next_prices();
+                market.next_prices()[0].1
+            })
+            .sum::<f64>()
+            / samples as f64;
+        assert!((mean - 1.0).abs() < 0.02, "mean price {mean}");
+    }
+}
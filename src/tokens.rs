@@ -0,0 +1,160 @@
+use crate::config::{Config, TokenConfig};
+use crate::model::{PriceInfo, WalletState};
+use anyhow::{Result, anyhow};
+use std::collections::{BTreeMap, HashMap};
+
+/// Mint of wrapped SOL; swaps touching it need Jupiter to wrap/unwrap.
+const WRAPPED_SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub symbol: String,
+    pub mint: String,
+    pub decimals: u8,
+    /// Normalized Hermes feed id quoting this token in USD, if any.
+    pub feed_id: Option<String>,
+    /// Smallest amount a strategy will trade, in whole tokens.
+    pub min_trade_amount: f64,
+}
+
+impl Token {
+    pub fn is_wrapped_sol(&self) -> bool {
+        self.mint == WRAPPED_SOL_MINT
+    }
+
+    /// Converts whole tokens to base units, rounding to the nearest unit.
+    /// Negative and non-finite amounts convert to zero.
+    pub fn to_base_units(&self, amount: f64) -> u64 {
+        if !amount.is_finite() {
+            return 0;
+        }
+        (amount * self.unit_scale()).round() as u64
+    }
+
+    /// Converts base units to whole tokens, for display and decisions only.
+    pub fn to_whole(&self, units: u64) -> f64 {
+        units as f64 / self.unit_scale()
+    }
+
+    fn unit_scale(&self) -> f64 {
+        10f64.powi(i32::from(self.decimals))
+    }
+
+    /// Latest USD price of the token. Tokens without a feed are treated as
+    /// worth one USD.
+    pub fn usd_price(&self, latest_prices: &HashMap<String, PriceInfo>) -> Option<f64> {
+        match &self.feed_id {
+            Some(feed_id) => latest_prices.get(feed_id).map(|price| price.value),
+            None => Some(1.0),
+        }
+    }
+}
+
+/// Base/quote pair a strategy trades. Prices are expressed as quote per base.
+#[derive(Debug, Clone)]
+pub struct TradingPair {
+    pub base: Token,
+    pub quote: Token,
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenRegistry {
+    tokens: Vec<Token>,
+}
+
+impl TokenRegistry {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let tokens = config
+            .tokens
+            .iter()
+            .map(|token| resolve_token(config, token))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { tokens })
+    }
+
+    pub fn by_symbol(&self, symbol: &str) -> Option<&Token> {
+        self.tokens.iter().find(|token| token.symbol == symbol)
+    }
+
+    pub fn by_mint(&self, mint: &str) -> Option<&Token> {
+        self.tokens.iter().find(|token| token.mint == mint)
+    }
+
+    pub fn wrapped_sol(&self) -> Option<&Token> {
+        self.tokens.iter().find(|token| token.is_wrapped_sol())
+    }
+
+    /// Builds a wallet from balances keyed by token symbol.
+    pub fn wallet(&self, balances: &BTreeMap<String, f64>) -> Result<WalletState> {
+        let mut wallet = WalletState::default();
+        for (symbol, amount) in balances {
+            let token = self
+                .by_symbol(symbol)
+                .ok_or_else(|| anyhow!("unknown token `{symbol}`"))?;
+            wallet.credit(token, token.to_base_units(*amount))?;
+        }
+        Ok(wallet)
+    }
+
+    pub fn pair(&self, base: &str, quote: &str) -> Result<TradingPair> {
+        let lookup = |symbol: &str| {
+            self.by_symbol(symbol)
+                .cloned()
+                .ok_or_else(|| anyhow!("unknown token `{symbol}`"))
+        };
+        Ok(TradingPair {
+            base: lookup(base)?,
+            quote: lookup(quote)?,
+        })
+    }
+}
+
+fn resolve_token(config: &Config, token: &TokenConfig) -> Result<Token> {
+    let feed_id = token
+        .feed
+        .as_deref()
+        .map(|feed| {
+            config
+                .hermes
+                .feed_id(&format!("tokens.{}.feed", token.symbol), feed)
+        })
+        .transpose()?;
+    Ok(Token {
+        symbol: token.symbol.clone(),
+        mint: token.mint.clone(),
+        decimals: token.decimals,
+        feed_id,
+        min_trade_amount: token.min_trade_amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usdc() -> Token {
+        Token {
+            symbol: "USDC".to_string(),
+            mint: "EPjFWdd5AufqSSqeMqejdX3tqZZzcny9qE8P4AQV7B7".to_string(),
+            decimals: 6,
+            feed_id: None,
+            min_trade_amount: 0.01,
+        }
+    }
+
+    #[test]
+    fn base_units_round_to_the_nearest_unit() {
+        let usdc = usdc();
+        assert_eq!(usdc.to_base_units(1.5), 1_500_000);
+        assert_eq!(usdc.to_base_units(0.000_000_6), 1);
+        assert_eq!(usdc.to_whole(2_500_000), 2.5);
+    }
+
+    #[test]
+    fn negative_and_non_finite_amounts_convert_to_zero() {
+        let usdc = usdc();
+        for amount in [-1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(usdc.to_base_units(amount), 0, "{amount}");
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use crate::backtest::{self, EquityPoint, PricePoint};
+use crate::config::{Config, WalkForwardArgs};
+use crate::metrics::Metrics;
+use crate::sweep::{self, Objective, SweepSpec};
+use anyhow::{Result, bail};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub strategy: String,
+    pub objective: Objective,
+    pub windows: Vec<Window>,
+    /// Out-of-sample equity of every window chained end to end, each window
+    /// starting from the equity the previous one ended with.
+    pub equity_curve: Vec<EquityPoint>,
+    pub total_return: Option<f64>,
+    pub max_drawdown: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Window {
+    pub in_sample: Span,
+    pub out_of_sample: Span,
+    /// Best parameter set on the in-sample slice, or `None` if no set scored.
+    pub params: Option<toml::Table>,
+    pub in_sample_score: Option<f64>,
+    pub metrics: Option<Metrics>,
+    /// Why the out-of-sample backtest failed, leaving `metrics` empty.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Span {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+pub fn run_cli(config: &Config, args: &WalkForwardArgs) -> Result<()> {
+    let spec = SweepSpec::from_file(&args.spec)?;
+    let prices = backtest::load_prices(config, &args.history)?;
+    let report = run(
+        config,
+        &spec,
+        &prices,
+        args.in_sample,
+        args.out_of_sample,
+        args.warmup,
+        args.anchored,
+    )?;
+
+    let time = |time: DateTime<Utc>| time.format("%Y-%m-%d %H:%M").to_string();
+    let optional = |value: Option<f64>, scale: f64| {
+        value
+            .map(|value| format!("{:.3}", value * scale))
+            .unwrap_or_else(|| "--".to_string())
+    };
+    println!(
+        "{:>6}  {:<16}  {:<16}  {:>10}  {:>9}  {:>8}  {:>6}  params",
+        "window", "in-sample from", "out-of-sample to", "is score", "return", "max dd", "trips"
+    );
+    for (index, window) in report.windows.iter().enumerate() {
+        let metrics = window.metrics.as_ref();
+        println!(
+            "{:>6}  {:<16}  {:<16}  {:>10}  {:>8}%  {:>7}%  {:>6}  {}",
+            index + 1,
+            time(window.in_sample.start),
+            time(window.out_of_sample.end),
+            optional(window.in_sample_score, 1.0),
+            optional(metrics.map(|metrics| metrics.total_return), 100.0),
+            optional(metrics.map(|metrics| metrics.max_drawdown), 100.0),
+            metrics.map_or(0, |metrics| metrics.round_trips),
+            match (&window.params, &window.error) {
+                (Some(params), Some(error)) =>
+                    format!("{} (failed: {error})", sweep::describe(params)),
+                (Some(params), None) => sweep::describe(params),
+                (None, _) => "no parameter set scored".to_string(),
+            }
+        );
+    }
+    match (report.equity_curve.first(), report.equity_curve.last()) {
+        (Some(first), Some(last)) => eprintln!(
+            "{} out of sample: {:.2} → {:.2} USD ({}%), max drawdown {:.2}%",
+            report.strategy,
+            first.equity_usd,
+            last.equity_usd,
+            optional(report.total_return, 100.0),
+            report.max_drawdown * 100.0
+        ),
+        _ => eprintln!("{} never traded out of sample", report.strategy),
+    }
+
+    match &args.output {
+        Some(path) => backtest::write_json(Some(path), &report),
+        None => Ok(()),
+    }
+}
+
+/// Splits `prices` into consecutive out-of-sample windows of `out_of_sample`,
+/// each preceded by an in-sample window of `in_sample`, or by everything
+/// since the start when `anchored`. The spec's search picks the best
+/// parameters on each in-sample slice, which then trade the out-of-sample
+/// slice from the strategy's configured balances, after replaying the last
+/// `warmup` of the in-sample slice without trading. A window whose
+/// out-of-sample backtest fails is kept with its error and no metrics.
+pub fn run(
+    config: &Config,
+    spec: &SweepSpec,
+    prices: &[PricePoint],
+    in_sample: TimeDelta,
+    out_of_sample: TimeDelta,
+    warmup: TimeDelta,
+    anchored: bool,
+) -> Result<Report> {
+    let strategy = spec.target(config)?;
+    let (Some(first), Some(last)) = (prices.first(), prices.last()) else {
+        bail!("the price history is empty");
+    };
+
+    let mut windows = Vec::new();
+    let mut curves = Vec::new();
+    for (fit, test) in spans(first.time, last.time, in_sample, out_of_sample, anchored) {
+        let best = sweep::run(config, spec, slice(prices, fit))?
+            .into_iter()
+            .next()
+            .filter(|trial| trial.score.is_some());
+        let Some(best) = best else {
+            windows.push(Window {
+                in_sample: fit,
+                out_of_sample: test,
+                params: None,
+                in_sample_score: None,
+                metrics: None,
+                error: None,
+            });
+            continue;
+        };
+        let candidate = sweep::candidate_config(config, strategy, &best.params);
+        let warmup = Span {
+            start: (test.start - warmup).max(fit.start),
+            end: test.start,
+        };
+        let (metrics, error) =
+            match backtest::run_with_warmup(&candidate, slice(prices, warmup), slice(prices, test))
+            {
+                Ok(report) => {
+                    let report = report.strategies.into_iter().next();
+                    let (metrics, curve) = report
+                        .map(|report| (report.metrics, report.equity_curve))
+                        .unwrap_or_default();
+                    curves.push(curve);
+                    (metrics, None)
+                }
+                Err(error) => (None, Some(format!("{error:#}"))),
+            };
+        windows.push(Window {
+            in_sample: fit,
+            out_of_sample: test,
+            params: Some(best.params),
+            in_sample_score: best.score,
+            metrics,
+            error,
+        });
+    }
+    if windows.is_empty() {
+        bail!(
+            "the price history spans less than the {}s in-sample window",
+            in_sample.num_seconds()
+        );
+    }
+
+    let equity_curve = stitch(curves);
+    let total_return = match (equity_curve.first(), equity_curve.last()) {
+        (Some(first), Some(last)) if first.equity_usd > 0.0 => {
+            Some(last.equity_usd / first.equity_usd - 1.0)
+        }
+        _ => None,
+    };
+    let mut peak = 0.0_f64;
+    let mut max_drawdown = 0.0_f64;
+    for point in &equity_curve {
+        peak = peak.max(point.equity_usd);
+        if peak > 0.0 {
+            max_drawdown = max_drawdown.max(1.0 - point.equity_usd / peak);
+        }
+    }
+
+    Ok(Report {
+        strategy: spec.strategy.clone(),
+        objective: spec.objective,
+        windows,
+        equity_curve,
+        total_return,
+        max_drawdown,
+    })
+}
+
+/// In-sample and out-of-sample spans of every window whose out-of-sample
+/// span starts by `last`.
+fn spans(
+    first: DateTime<Utc>,
+    last: DateTime<Utc>,
+    in_sample: TimeDelta,
+    out_of_sample: TimeDelta,
+    anchored: bool,
+) -> Vec<(Span, Span)> {
+    let mut spans = Vec::new();
+    let mut test_start = first + in_sample;
+    while test_start <= last {
+        let fit = Span {
+            start: if anchored {
+                first
+            } else {
+                test_start - in_sample
+            },
+            end: test_start,
+        };
+        let test = Span {
+            start: test_start,
+            end: test_start + out_of_sample,
+        };
+        test_start = test.end;
+        spans.push((fit, test));
+    }
+    spans
+}
+
+/// Prices at or after `span.start` and before `span.end`.
+fn slice(prices: &[PricePoint], span: Span) -> &[PricePoint] {
+    let start = prices.partition_point(|point| point.time < span.start);
+    let end = prices.partition_point(|point| point.time < span.end);
+    &prices[start..end]
+}
+
+/// Chains equity curves by scaling each one to start where the last ended.
+fn stitch(curves: Vec<Vec<EquityPoint>>) -> Vec<EquityPoint> {
+    let mut stitched = Vec::<EquityPoint>::new();
+    for curve in curves {
+        let Some(first) = curve.first() else {
+            continue;
+        };
+        let scale = match stitched.last() {
+            Some(last) if first.equity_usd > 0.0 => last.equity_usd / first.equity_usd,
+            _ => 1.0,
+        };
+        stitched.extend(curve.iter().map(|point| EquityPoint {
+            time: point.time,
+            equity_usd: point.equity_usd * scale,
+        }));
+    }
+    stitched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hour(hours: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::UNIX_EPOCH + TimeDelta::hours(hours)
+    }
+
+    fn point(hours: i64, equity_usd: f64) -> EquityPoint {
+        EquityPoint {
+            time: hour(hours),
+            equity_usd,
+        }
+    }
+
+    fn bounds(spans: &[(Span, Span)]) -> Vec<(i64, i64, i64)> {
+        spans
+            .iter()
+            .map(|(fit, test)| {
+                assert_eq!(fit.end, test.start);
+                let hours = |time: DateTime<Utc>| (time - hour(0)).num_hours();
+                (hours(fit.start), hours(test.start), hours(test.end))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rolling_windows_slide_by_the_out_of_sample_length() {
+        let spans = spans(
+            hour(0),
+            hour(10),
+            TimeDelta::hours(4),
+            TimeDelta::hours(3),
+            false,
+        );
+        assert_eq!(bounds(&spans), [(0, 4, 7), (3, 7, 10), (6, 10, 13)]);
+    }
+
+    #[test]
+    fn anchored_windows_fit_from_the_start() {
+        let spans = spans(
+            hour(0),
+            hour(10),
+            TimeDelta::hours(4),
+            TimeDelta::hours(3),
+            true,
+        );
+        assert_eq!(bounds(&spans), [(0, 4, 7), (0, 7, 10), (0, 10, 13)]);
+    }
+
+    #[test]
+    fn history_shorter_than_the_in_sample_window_has_no_windows() {
+        let spans = spans(
+            hour(0),
+            hour(3),
+            TimeDelta::hours(4),
+            TimeDelta::hours(3),
+            false,
+        );
+        assert!(spans.is_empty());
+    }
+
+    #[test]
+    fn failed_out_of_sample_window_is_recorded_and_the_run_continues() {
+        let config: Config = toml::from_str(
+            r#"
+            [[strategies]]
+            kind = "trend_follow"
+            base = "SOL"
+            quote = "USDC"
+            initial_balances = { SOL = 1.0, USDC = 100.0 }
+            params = { threshold = 0.002, sell_base_amount = 0.1, buy_quote_amount = 10.0 }
+            "#,
+        )
+        .unwrap();
+        let spec: SweepSpec = toml::from_str(
+            r#"
+            strategy = "trend_follow"
+
+            [params]
+            threshold = [0.001, 0.002]
+            "#,
+        )
+        .unwrap();
+        let feed_id = config.hermes.feed_id("test", "SOL/USD").unwrap();
+        // No rows between 4h and 7h, so the first test window has nothing
+        // to price the pair with.
+        let prices = (0..60)
+            .filter(|step| !(24..42).contains(step))
+            .map(|step| {
+                let time = hour(0) + TimeDelta::minutes(10 * step);
+                let value = if step % 2 == 0 { 100.0 } else { 102.0 };
+                PricePoint {
+                    time,
+                    feed: "SOL/USD".to_string(),
+                    feed_id: feed_id.clone(),
+                    price: crate::model::PriceInfo {
+                        value,
+                        publish_time: Some(time.timestamp()),
+                    },
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let report = run(
+            &config,
+            &spec,
+            &prices,
+            TimeDelta::hours(4),
+            TimeDelta::hours(3),
+            TimeDelta::hours(1),
+            false,
+        )
+        .unwrap();
+        assert_eq!(report.windows.len(), 2);
+        let (failed, traded) = (&report.windows[0], &report.windows[1]);
+        assert!(failed.params.is_some() && failed.metrics.is_none());
+        assert!(failed.error.as_deref().unwrap().contains("SOL/USD"));
+        assert!(traded.metrics.is_some() && traded.error.is_none());
+        assert_eq!(report.equity_curve.first().unwrap().time, hour(7));
+    }
+
+    #[test]
+    fn stitch_scales_each_curve_to_the_last_equity() {
+        let stitched = stitch(vec![
+            vec![point(0, 100.0), point(1, 120.0)],
+            Vec::new(),
+            vec![point(2, 50.0), point(3, 40.0)],
+        ]);
+        let equity = stitched
+            .iter()
+            .map(|point| point.equity_usd)
+            .collect::<Vec<_>>();
+        assert_eq!(equity, [100.0, 120.0, 120.0, 96.0]);
+        assert_eq!(stitched[3].time, hour(3));
+    }
+}
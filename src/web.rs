@@ -1,30 +1,108 @@
+use crate::config::Config;
 use crate::model::AppState;
-use crate::simulation::publish_time_to_string;
-use axum::{extract::State, response::Html};
+use crate::simulation::{format_timestamp, publish_time_to_string};
+use crate::tokens::TokenRegistry;
+use axum::{Json, extract::State, response::Html};
+use serde_json::{Value, json};
+use std::sync::Arc;
 
-pub async fn index(State(state): State<AppState>) -> Html<String> {
-    let data = state.lock().await;
-    let latest_price_value = data.latest_price.as_ref().map(|price| price.value);
-    let latest_price_display = latest_price_value
-        .map(|price| format!("{:.4}", price))
-        .unwrap_or_else(|| "waiting…".to_string());
+#[derive(Clone)]
+pub struct WebState {
+    pub app: AppState,
+    pub config: Arc<Config>,
+    pub tokens: Arc<TokenRegistry>,
+}
+
+pub async fn index(State(state): State<WebState>) -> Html<String> {
+    let data = state.app.lock().await;
+
+    let feed_rows = state
+        .config
+        .hermes
+        .feeds
+        .iter()
+        .map(|feed| {
+            let latest = data.latest_prices.get(&feed.normalized_id());
+            let price_display = latest
+                .map(|price| format!("{:.4}", price.value))
+                .unwrap_or_else(|| "waiting…".to_string());
+            let publish_time_display = latest
+                .map(|price| publish_time_to_string(price.publish_time))
+                .unwrap_or_else(|| "unknown".to_string());
 
-    let publish_time_display = data
-        .latest_price
-        .as_ref()
-        .map(|price| publish_time_to_string(price.publish_time))
-        .unwrap_or_else(|| "unknown".to_string());
+            format!(
+                "<tr>\
+                    <td>{}</td>\
+                    <td>{}</td>\
+                    <td>{}</td>\
+                </tr>",
+                feed.name, price_display, publish_time_display
+            )
+        })
+        .collect::<String>();
 
     let strategy_rows = data
         .strategies
         .iter()
         .map(|strategy| {
-            let sol = format!("{:.4}", strategy.wallet.sol);
-            let usdc = format!("{:.2}", strategy.wallet.usdc);
-            let total_usd = latest_price_value
-                .map(|price| strategy.wallet.sol * price + strategy.wallet.usdc)
+            let pair = &strategy.pair;
+            let pair_label = format!("{} / {}", pair.base.symbol, pair.quote.symbol);
+            let holdings = strategy
+                .wallet
+                .holdings()
+                .map(|(mint, units)| match state.tokens.by_mint(mint) {
+                    Some(token) => {
+                        format!("{:.4} {}", token.to_whole(units), token.symbol)
+                    }
+                    None => format!("{units} {mint}"),
+                })
+                .collect::<Vec<_>>();
+            let holdings = if holdings.is_empty() {
+                "--".to_string()
+            } else {
+                holdings.join("<br />")
+            };
+            let total_usd = strategy
+                .wallet
+                .value_usd(&state.tokens, &data.latest_prices)
                 .map(|total| format!("{:.2}", total))
                 .unwrap_or_else(|| "--".to_string());
+            let status = match strategy.risk.as_ref().and_then(|risk| risk.halt()) {
+                Some(halt) => {
+                    let until = halt
+                        .until
+                        .map(|until| format!(" until {}", until.format("%Y-%m-%d %H:%M:%S UTC")))
+                        .unwrap_or_default();
+                    format!(
+                        "Halted since {}{until}: {}",
+                        halt.since.format("%Y-%m-%d %H:%M:%S UTC"),
+                        halt.reason
+                    )
+                }
+                None => "Active".to_string(),
+            };
+            let orders = strategy
+                .orders
+                .orders()
+                .iter()
+                .map(|order| order.describe(pair))
+                .collect::<Vec<_>>();
+            let orders = if orders.is_empty() {
+                "--".to_string()
+            } else {
+                orders.join("<br />")
+            };
+
+            let alpha = strategy
+                .benchmark_results()
+                .iter()
+                .map(|benchmark| format!("vs {}: {}", benchmark.name, percent(benchmark.alpha)))
+                .collect::<Vec<_>>();
+            let alpha = if alpha.is_empty() {
+                "--".to_string()
+            } else {
+                alpha.join("<br />")
+            };
 
             format!(
                 "<tr>\
@@ -32,11 +110,52 @@ pub async fn index(State(state): State<AppState>) -> Html<String> {
                     <td>{}</td>\
                     <td>{}</td>\
                     <td>{}</td>\
+                    <td>{}</td>\
+                    <td>{}</td>\
+                    <td>{}</td>\
                 </tr>",
-                strategy.id.label(),
-                sol,
-                usdc,
-                total_usd
+                strategy.strategy.label(),
+                pair_label,
+                holdings,
+                total_usd,
+                alpha,
+                orders,
+                status
+            )
+        })
+        .collect::<String>();
+
+    let performance_rows = data
+        .strategies
+        .iter()
+        .map(|strategy| {
+            let quote = &strategy.pair.quote.symbol;
+            let cells = match strategy.metrics() {
+                Some(metrics) => [
+                    percent(Some(metrics.total_return)),
+                    format!("{:.2} {quote}", metrics.realized_pnl),
+                    format!("{:.2} {quote}", metrics.unrealized_pnl),
+                    percent(metrics.annualized_volatility),
+                    ratio(metrics.sharpe),
+                    ratio(metrics.sortino),
+                    percent(Some(-metrics.max_drawdown)),
+                    percent(metrics.win_rate),
+                    metrics
+                        .average_trade
+                        .map(|profit| format!("{profit:.4} {quote}"))
+                        .unwrap_or_else(|| "--".to_string()),
+                    ratio(metrics.turnover),
+                    percent(metrics.exposure),
+                ],
+                None => std::array::from_fn(|_| "--".to_string()),
+            };
+            format!(
+                "<tr><td>{}</td>{}</tr>",
+                strategy.strategy.label(),
+                cells
+                    .iter()
+                    .map(|cell| format!("<td>{cell}</td>"))
+                    .collect::<String>()
             )
         })
         .collect::<String>();
@@ -46,14 +165,46 @@ pub async fn index(State(state): State<AppState>) -> Html<String> {
         .iter()
         .rev()
         .map(|record| {
-            let gas_display = record
-                .gas_lamports
-                .map(|value| value.to_string())
-                .unwrap_or_else(|| "--".to_string());
+            let fees = &record.fees;
+            let output_fee = fees.output_fee();
+            let fees_display = format!(
+                "{} base + {} priority + {} tip lamports",
+                fees.base_fee_lamports, fees.priority_fee_lamports, fees.jito_tip_lamports
+            );
+            let fees_display = if output_fee > 0 {
+                format!(
+                    "{fees_display}<br />{:.4} {}",
+                    record.output_token.to_whole(output_fee),
+                    record.output_token.symbol
+                )
+            } else {
+                fees_display
+            };
             let price_impact_display = record
                 .price_impact_pct
                 .map(|value| format!("{:.4}", value * 100.0))
                 .unwrap_or_else(|| "--".to_string());
+            let profit_display = record
+                .realized_profit
+                .map(|value| format!("{value:.4} {}", record.output_token.symbol))
+                .unwrap_or_else(|| "--".to_string());
+            let delay_display = record
+                .fill_delay_ms
+                .map(|millis| {
+                    let drift = (record.price / record.decision_price - 1.0) * 100.0;
+                    format!("{millis} ms ({drift:+.3}%)")
+                })
+                .unwrap_or_else(|| "--".to_string());
+            let indicators_display = if record.indicators.is_empty() {
+                "--".to_string()
+            } else {
+                record
+                    .indicators
+                    .iter()
+                    .map(|(name, value)| format!("{name}={value:.4}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
 
             format!(
                 "<tr>\
@@ -65,33 +216,92 @@ pub async fn index(State(state): State<AppState>) -> Html<String> {
                     <td>{:.4} {}</td>\
                     <td>{}</td>\
                     <td>{}</td>\
+                    <td>{}</td>\
+                    <td>{}</td>\
+                    <td>{}</td>\
                 </tr>",
-                record.timestamp,
-                record.strategy.label(),
+                format_timestamp(record.timestamp),
+                record.strategy,
                 record.direction,
                 record.price,
-                record.amount_in,
-                record.input_token.symbol(),
-                record.amount_out,
-                record.output_token.symbol(),
-                gas_display,
-                price_impact_display
+                record.input_token.to_whole(record.amount_in),
+                record.input_token.symbol,
+                record.output_token.to_whole(record.amount_out),
+                record.output_token.symbol,
+                fees_display,
+                price_impact_display,
+                delay_display,
+                profit_display,
+                indicators_display
             )
         })
         .collect::<String>();
 
+    let title = state
+        .config
+        .hermes
+        .feeds
+        .iter()
+        .map(|feed| feed.name.as_str())
+        .collect::<Vec<_>>()
+        .join(" · ");
     Html(build_page(
-        latest_price_display,
-        publish_time_display,
+        &title,
+        feed_rows,
         strategy_rows,
+        performance_rows,
         history_rows,
     ))
 }
 
+fn percent(value: Option<f64>) -> String {
+    value
+        .map(|value| format!("{:+.2}%", value * 100.0))
+        .unwrap_or_else(|| "--".to_string())
+}
+
+fn ratio(value: Option<f64>) -> String {
+    value
+        .map(|value| format!("{value:.2}"))
+        .unwrap_or_else(|| "--".to_string())
+}
+
+pub async fn strategies(State(state): State<WebState>) -> Json<Value> {
+    let data = state.app.lock().await;
+    let strategies = data
+        .strategies
+        .iter()
+        .map(|strategy| {
+            let holdings = strategy
+                .wallet
+                .holdings()
+                .map(|(mint, units)| match state.tokens.by_mint(mint) {
+                    Some(token) => (token.symbol.clone(), json!(token.to_whole(units))),
+                    None => (mint.to_string(), json!(units)),
+                })
+                .collect::<serde_json::Map<_, _>>();
+            json!({
+                "label": strategy.strategy.label(),
+                "pair": format!("{}/{}", strategy.pair.base.symbol, strategy.pair.quote.symbol),
+                "holdings": holdings,
+                "total_usd": strategy.wallet.value_usd(&state.tokens, &data.latest_prices),
+                "parameters": strategy.strategy.parameters(),
+                "state": strategy.strategy.state(),
+                "risk": strategy.risk.as_ref().map(|risk| risk.status()),
+                "orders": strategy.orders.snapshot(),
+                "metrics": strategy.metrics(),
+                "benchmarks": strategy.benchmark_results(),
+            })
+        })
+        .collect::<Vec<_>>();
+    Json(Value::Array(strategies))
+}
+
 fn build_page(
-    latest_price: String,
-    publish_time: String,
+    title: &str,
+    feed_rows: String,
     strategy_rows: String,
+    performance_rows: String,
     history_rows: String,
 ) -> String {
     format!(
@@ -100,7 +310,7 @@ fn build_page(
 <html lang="en">
 <head>
     <meta charset="utf-8" />
-    <title>SOL / USDC Simulation</title>
+    <title>{title} Simulation</title>
     <style>
         body {{
             font-family: Arial, sans-serif;
@@ -160,10 +370,19 @@ fn build_page(
 </head>
 <body>
     <div class="card">
-        <h1>SOL / USDC Live Simulation</h1>
-        <div class="chip">Last publish time: {publish_time}</div>
-        <div class="metric">${latest_price}</div>
-        <div class="metric-label">Latest SOL price (USD)</div>
+        <h1>{title} Live Simulation</h1>
+        <table>
+            <thead>
+                <tr>
+                    <th>Feed</th>
+                    <th>Price (USD)</th>
+                    <th>Last publish time</th>
+                </tr>
+            </thead>
+            <tbody>
+                {feed_rows}
+            </tbody>
+        </table>
     </div>
     <div class="card">
         <h2>Strategy Wallets</h2>
@@ -171,9 +390,12 @@ fn build_page(
             <thead>
                 <tr>
                     <th>Strategy</th>
-                    <th>SOL</th>
-                    <th>USDC</th>
+                    <th>Pair</th>
+                    <th>Holdings</th>
                     <th>Total USD</th>
+                    <th>Alpha</th>
+                    <th>Resting Orders</th>
+                    <th>Status</th>
                 </tr>
             </thead>
             <tbody>
@@ -181,6 +403,30 @@ fn build_page(
             </tbody>
         </table>
     </div>
+    <div class="card">
+        <h2>Performance</h2>
+        <table>
+            <thead>
+                <tr>
+                    <th>Strategy</th>
+                    <th>Return</th>
+                    <th>Realized PnL</th>
+                    <th>Unrealized PnL</th>
+                    <th>Ann. Volatility</th>
+                    <th>Sharpe</th>
+                    <th>Sortino</th>
+                    <th>Max Drawdown</th>
+                    <th>Win Rate</th>
+                    <th>Avg Trade</th>
+                    <th>Turnover</th>
+                    <th>Exposure</th>
+                </tr>
+            </thead>
+            <tbody>
+                {performance_rows}
+            </tbody>
+        </table>
+    </div>
     <div class="card">
         <h2>Swap History</h2>
         <table>
@@ -189,11 +435,14 @@ fn build_page(
                     <th>Timestamp</th>
                     <th>Strategy</th>
                     <th>Direction</th>
-                    <th>Price (USD)</th>
+                    <th>Price (quote)</th>
                     <th>Amount In</th>
                     <th>Amount Out</th>
-                    <th>Gas (lamports)</th>
+                    <th>Fees</th>
                     <th>Price Impact (%)</th>
+                    <th>Fill Delay</th>
+                    <th>Profit</th>
+                    <th>Indicators</th>
                 </tr>
             </thead>
             <tbody>
@@ -204,9 +453,9 @@ fn build_page(
 </body>
 </html>
 "#,
-        publish_time = publish_time,
-        latest_price = latest_price,
+        feed_rows = feed_rows,
         strategy_rows = strategy_rows,
+        performance_rows = performance_rows,
         history_rows = history_rows
     )
 }